@@ -39,7 +39,10 @@ use crate::{
         middlewares::cheatcode::CHEATCODE_ADDRESS,
         mutator::AccessPattern,
         onchain::{abi_decompiler::fetch_abi_heimdall, flashloan::register_borrow_txn, BLACKLIST_ADDR},
-        presets::Preset,
+        presets::{
+            signature_seed::{known_signers, seed_signature_calldata},
+            Preset,
+        },
         types::{
             fixed_address,
             EVMAddress,
@@ -384,6 +387,17 @@ where
                 .evmstate
                 .set_balance(caller, EVMU256::from(INITIAL_BALANCE));
         }
+
+        // Also register `known_signers()`'s addresses as callers, so a seeded call
+        // made "as" one of them actually has `input.caller` equal a known signer and
+        // `seed_signature_calldata` below has a chance to fire.
+        for (caller, _) in known_signers() {
+            self.state.add_caller(&caller);
+            self.executor
+                .host
+                .evmstate
+                .set_balance(caller, EVMU256::from(INITIAL_BALANCE));
+        }
     }
 
     pub fn setup_contract_callers(&mut self) {
@@ -465,6 +479,22 @@ where
             let corpus_dir = format!("{}/corpus", self.work_dir.as_str());
             dump_txn!(corpus_dir, &input)
         }
+
+        // Functions gated by ecrecover (ERC-2612 permit, EIP-712 meta-tx) are
+        // effectively unreachable by random mutation, so when the input's caller is
+        // one of `known_signers()`, seed one extra input whose signature tail is
+        // signed by that caller's key.
+        if let Some(data) = &input.data {
+            if let Some(seeded_calldata) =
+                seed_signature_calldata(abi, &data.get_bytes(), input.caller, &known_signers())
+            {
+                let mut seeded_data = data.clone();
+                seeded_data.set_bytes(seeded_calldata);
+                let mut seeded_input = input.clone();
+                seeded_input.data = Some(seeded_data);
+                add_input_to_corpus!(self.state, &mut self.scheduler, seeded_input, artifacts);
+            }
+        }
         #[cfg(feature = "use_presets")]
         {
             let presets = self.presets.clone();
@@ -0,0 +1,320 @@
+use std::{cell::RefCell, collections::HashMap};
+
+use bytes::Bytes;
+use revm_interpreter::Interpreter;
+use serde::{Deserialize, Serialize};
+use tracing::info;
+
+use crate::evm::{
+    corpus_initializer::ABIMap,
+    host::FuzzHost,
+    input::{ConciseEVMInput, EVMInputT},
+    middlewares::middleware::{Middleware, MiddlewareType},
+    onchain::precompiles::is_precompile,
+    types::{convert_u256_to_h160, EVMAddress},
+};
+
+/// Whether `addr` has any bytecode of its own to run. A CALL-family opcode only opens
+/// a fresh `Interpreter` frame (and so only ever has a matching RETURN/STOP/REVERT for
+/// `finish_top` to close) when its target has code; a plain EOA has none.
+fn has_code<VS, I, S, SC>(host: &mut FuzzHost<VS, I, S, SC>, addr: EVMAddress) -> bool {
+    host.code(addr).map(|(code, _)| !code.is_empty()).unwrap_or(false)
+}
+
+/// Opcode of every call-like instruction the recorder tracks, mirroring how
+/// `Flashloan::on_step` distinguishes value-transferring calls from everything else.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub enum CallType {
+    Call,
+    CallCode,
+    DelegateCall,
+    StaticCall,
+    Create,
+    Create2,
+}
+
+impl CallType {
+    pub(crate) fn from_opcode(op: u8) -> Option<Self> {
+        match op {
+            0xf1 => Some(Self::Call),
+            0xf2 => Some(Self::CallCode),
+            0xf4 => Some(Self::DelegateCall),
+            0xfa => Some(Self::StaticCall),
+            0xf0 => Some(Self::Create),
+            0xf5 => Some(Self::Create2),
+            _ => None,
+        }
+    }
+}
+
+/// One node of a decoded call trace: a single CALL/STATICCALL/DELEGATECALL/CREATE and
+/// everything it in turn called. Addresses and selectors are raw here; [`decode_node`]
+/// resolves them to the human-readable names used when a bug report is printed.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct CallTraceNode {
+    pub call_type: CallType,
+    pub target: EVMAddress,
+    pub value: u128,
+    pub gas: u64,
+    pub depth: usize,
+    pub success: bool,
+    pub revert_reason: Option<String>,
+    pub input: Bytes,
+    pub output: Bytes,
+    pub children: Vec<CallTraceNode>,
+}
+
+impl CallTraceNode {
+    fn new(
+        call_type: CallType,
+        target: EVMAddress,
+        value: u128,
+        gas: u64,
+        depth: usize,
+        input: Bytes,
+    ) -> Self {
+        Self {
+            call_type,
+            target,
+            value,
+            gas,
+            depth,
+            success: false,
+            revert_reason: None,
+            input,
+            output: Bytes::new(),
+            children: vec![],
+        }
+    }
+}
+
+/// A decoded, human-readable version of a [`CallTraceNode`] produced via the
+/// `ABIMap` 4-byte-selector table and `EVMInitializationArtifacts::address_to_name`.
+#[derive(Clone, Debug)]
+pub struct DecodedCallTraceNode {
+    pub call_type: CallType,
+    pub target_name: String,
+    pub function_name: String,
+    pub value: u128,
+    pub success: bool,
+    pub revert_reason: Option<String>,
+    pub depth: usize,
+    pub children: Vec<DecodedCallTraceNode>,
+}
+
+pub fn decode_node(
+    node: &CallTraceNode,
+    abi_map: &ABIMap,
+    address_to_name: &HashMap<EVMAddress, String>,
+) -> DecodedCallTraceNode {
+    let target_name = address_to_name
+        .get(&node.target)
+        .cloned()
+        .unwrap_or_else(|| format!("{:?}", node.target));
+
+    let function_name = if node.input.len() >= 4 {
+        let mut selector = [0u8; 4];
+        selector.copy_from_slice(&node.input[..4]);
+        abi_map
+            .get(&selector)
+            .map(|abi| abi.function_name.clone())
+            .unwrap_or_else(|| format!("0x{}", hex::encode(selector)))
+    } else {
+        "!fallback!".to_string()
+    };
+
+    DecodedCallTraceNode {
+        call_type: node.call_type,
+        target_name,
+        function_name,
+        value: node.value,
+        success: node.success,
+        revert_reason: node.revert_reason.clone(),
+        depth: node.depth,
+        children: node
+            .children
+            .iter()
+            .map(|child| decode_node(child, abi_map, address_to_name))
+            .collect(),
+    }
+}
+
+/// Renders a decoded call tree as an indented, colorized reproduction trace, the way
+/// Foundry prints `forge test -vvvv` traces.
+pub fn render_trace(node: &DecodedCallTraceNode) -> String {
+    let mut out = String::new();
+    render_trace_into(node, &mut out);
+    out
+}
+
+fn render_trace_into(node: &DecodedCallTraceNode, out: &mut String) {
+    let indent = "  ".repeat(node.depth);
+    let status = if node.success {
+        "\x1b[32m[success]\x1b[0m"
+    } else {
+        "\x1b[31m[revert]\x1b[0m"
+    };
+    out.push_str(&format!(
+        "{indent}{:?} {}::{} {status}\n",
+        node.call_type, node.target_name, node.function_name
+    ));
+    if let Some(reason) = &node.revert_reason {
+        out.push_str(&format!("{indent}  reason: {reason}\n"));
+    }
+    for child in &node.children {
+        render_trace_into(child, out);
+    }
+}
+
+thread_local! {
+    // Finished first-level sub-calls of the transaction currently being executed,
+    // collected by `CallTraceRecorder` until the top-level frame ends and they become
+    // the children of the root node stored in `LAST_TRACE`.
+    static CURRENT_TRACE: RefCell<Vec<CallTraceNode>> = RefCell::new(vec![]);
+    static LAST_TRACE: RefCell<Option<CallTraceNode>> = RefCell::new(None);
+}
+
+/// Returns the root of the most recently completed transaction's call trace, if any
+/// was recorded. Oracles call this to attach a reproduction trace to a bug report.
+pub fn last_call_trace() -> Option<CallTraceNode> {
+    LAST_TRACE.with(|t| t.borrow().clone())
+}
+
+/// Middleware that records a tree of call-trace nodes for every
+/// CALL/STATICCALL/DELEGATECALL/CREATE the interpreter executes, so a reported
+/// invariant violation ships a readable reproduction trace instead of a bare PC.
+///
+/// This only has access to `on_step`, one opcode at a time, so entry is detected at
+/// the CALL-family opcode and exit is approximated at the next RETURN/REVERT/STOP seen
+/// at the same depth; it does not see the callee's actual return data.
+pub struct CallTraceRecorder<VS, I, S> {
+    stack: Vec<CallTraceNode>,
+    _phantom: std::marker::PhantomData<(VS, I, S)>,
+}
+
+impl<VS, I, S> Default for CallTraceRecorder<VS, I, S> {
+    fn default() -> Self {
+        Self {
+            stack: vec![],
+            _phantom: std::marker::PhantomData,
+        }
+    }
+}
+
+impl<VS, I, S> CallTraceRecorder<VS, I, S> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn finish_top(&mut self, interp: &Interpreter, success: bool, revert_reason: Option<String>) {
+        match self.stack.pop() {
+            Some(mut node) => {
+                node.success = success;
+                node.revert_reason = revert_reason;
+                match self.stack.last_mut() {
+                    Some(parent) => parent.children.push(node),
+                    None => CURRENT_TRACE.with(|t| t.borrow_mut().push(node)),
+                }
+            }
+            // Nothing on `self.stack` to pop means this RETURN/STOP/REVERT belongs to
+            // the top-level call itself: its entry is never observed as a CALL-family
+            // opcode (only a *caller's* frame pushes a node), so the root node is built
+            // here, adopting the first-level sub-calls collected in `CURRENT_TRACE`.
+            // Without this branch `LAST_TRACE` would silently keep holding whatever a
+            // previous, unrelated transaction left in it instead of reflecting this one.
+            None => {
+                let node = CallTraceNode {
+                    call_type: CallType::Call,
+                    target: interp.contract.address,
+                    value: 0,
+                    gas: interp.gas.remaining(),
+                    depth: 0,
+                    success,
+                    revert_reason,
+                    input: interp.contract.input.clone(),
+                    output: Bytes::new(),
+                    children: CURRENT_TRACE.with(|t| std::mem::take(&mut *t.borrow_mut())),
+                };
+                LAST_TRACE.with(|t| *t.borrow_mut() = Some(node));
+            }
+        }
+    }
+}
+
+impl<VS, I, S, SC> Middleware<VS, I, S, SC> for CallTraceRecorder<VS, I, S>
+where
+    I: EVMInputT + 'static,
+{
+    unsafe fn on_step(
+        &mut self,
+        interp: &mut Interpreter,
+        host: &mut FuzzHost<VS, I, S, SC>,
+        _s: &mut S,
+    ) {
+        // An abnormal halt (out-of-gas, INVALID, stack under/overflow, ...) never
+        // executes a RETURN/STOP/REVERT opcode of its own, so the match below would
+        // never see it and the node pushed for this frame would stay on `self.stack`
+        // forever, permanently desyncing every subsequent trace's depth and nesting
+        // for the rest of this process's lifetime. Treat it exactly like a REVERT.
+        if interp.instruction_result.is_error() {
+            self.finish_top(interp, false, Some(format!("{:?}", interp.instruction_result)));
+            return;
+        }
+
+        let op = *interp.instruction_pointer;
+        if let Some(call_type) = CallType::from_opcode(op) {
+            let value = match call_type {
+                CallType::Call | CallType::CallCode => {
+                    interp.stack.peek(2).map(|v| v.to::<u128>()).unwrap_or(0)
+                }
+                _ => 0,
+            };
+            let is_create = matches!(call_type, CallType::Create | CallType::Create2);
+            let target = if is_create {
+                EVMAddress::zero()
+            } else {
+                interp
+                    .stack
+                    .peek(1)
+                    .map(convert_u256_to_h160)
+                    .unwrap_or_default()
+            };
+
+            // A precompile runs natively and a plain no-code target (e.g. an EOA
+            // transfer) never opens an Interpreter frame of its own, so it will never
+            // hit a matching RETURN/STOP/REVERT below. Pushing a node for it here would
+            // leak onto `self.stack` forever, permanently desyncing every trace's
+            // depth/nesting built for the rest of the process -- and `last_call_trace()`
+            // feeds `TypedBugOracle`'s user-facing reproduction traces directly, so this
+            // would corrupt real output, not just internal state.
+            if !is_create && (is_precompile(target) || !has_code(host, target)) {
+                return;
+            }
+
+            let gas = interp.gas.remaining();
+            self.stack.push(CallTraceNode::new(
+                call_type,
+                target,
+                value,
+                gas,
+                // depth 0 is the top-level call's own root node
+                self.stack.len() + 1,
+                interp.contract.input.clone(),
+            ));
+            return;
+        }
+
+        match op {
+            // RETURN / STOP: the frame returning completed successfully.
+            0xf3 | 0x00 => self.finish_top(interp, true, None),
+            // REVERT: the returned bytes are not visible here, so the reason is a fixed
+            // "execution reverted" marker rather than the decoded revert string.
+            0xfd => self.finish_top(interp, false, Some("execution reverted".to_string())),
+            _ => {}
+        }
+    }
+
+    fn get_type(&self) -> MiddlewareType {
+        MiddlewareType::CallTrace
+    }
+}
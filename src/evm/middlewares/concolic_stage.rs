@@ -0,0 +1,404 @@
+use std::{
+    collections::{hash_map::DefaultHasher, HashSet, VecDeque},
+    fmt::Debug,
+    hash::{Hash, Hasher},
+    marker::PhantomData,
+};
+
+use revm_interpreter::Interpreter;
+use tracing::debug;
+use z3::{
+    ast::{Ast, BV},
+    Config,
+    Context,
+    SatResult,
+    Solver,
+};
+
+use crate::evm::{
+    host::FuzzHost,
+    input::EVMInputT,
+    middlewares::middleware::{Middleware, MiddlewareType},
+    types::EVMU256,
+};
+
+/// One byte of `EVMInput.data`: either pinned to a concrete value, or a free variable
+/// the solver is allowed to pick, identified by its offset into the calldata buffer.
+#[derive(Clone, Copy, Debug)]
+pub enum SymbolicByte {
+    Concrete(u8),
+    Symbolic(usize),
+}
+
+/// A single atomic comparison contributing to a path condition, built up as the
+/// interpreter steps through EQ/LT/GT/ISZERO opcodes over symbolic calldata.
+#[derive(Clone, Debug)]
+pub enum PathConstraint {
+    Eq(Vec<SymbolicByte>, Vec<SymbolicByte>),
+    Lt(Vec<SymbolicByte>, Vec<SymbolicByte>),
+    Gt(Vec<SymbolicByte>, Vec<SymbolicByte>),
+    IsZero(Vec<SymbolicByte>),
+    Not(Box<PathConstraint>),
+}
+
+/// Per-seed accumulated path condition: the prefix of comparisons the concrete
+/// execution actually took, in order. Negating the guard at one of these and
+/// conjoining it with the rest is how a not-taken branch gets turned into an SMT
+/// query.
+#[derive(Clone, Debug, Default)]
+pub struct PathCondition {
+    pub constraints: Vec<PathConstraint>,
+}
+
+impl PathCondition {
+    fn hash_prefix(prefix: &[PathConstraint]) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        for c in prefix {
+            format!("{c:?}").hash(&mut hasher);
+        }
+        hasher.finish()
+    }
+}
+
+/// Builds a big-endian bitvector out of symbolic calldata bytes, introducing one free
+/// 8-bit variable per distinct symbolic byte offset and concatenating concrete bytes
+/// as constants.
+fn to_bv<'ctx>(ctx: &'ctx Context, bytes: &[SymbolicByte]) -> BV<'ctx> {
+    let mut parts: Vec<BV<'ctx>> = bytes
+        .iter()
+        .map(|b| match b {
+            SymbolicByte::Concrete(v) => BV::from_u64(ctx, *v as u64, 8),
+            SymbolicByte::Symbolic(offset) => BV::new_const(ctx, format!("byte_{offset}"), 8),
+        })
+        .collect();
+    let mut acc = parts.remove(0);
+    for part in parts {
+        acc = acc.concat(&part);
+    }
+    acc
+}
+
+fn assert_constraint<'ctx>(ctx: &'ctx Context, solver: &Solver<'ctx>, constraint: &PathConstraint, negate: bool) {
+    let expr = match constraint {
+        PathConstraint::Eq(a, b) => to_bv(ctx, a)._eq(&to_bv(ctx, b)),
+        PathConstraint::Lt(a, b) => to_bv(ctx, a).bvult(&to_bv(ctx, b)),
+        PathConstraint::Gt(a, b) => to_bv(ctx, a).bvugt(&to_bv(ctx, b)),
+        PathConstraint::IsZero(a) => {
+            let bv = to_bv(ctx, a);
+            bv._eq(&BV::from_u64(ctx, 0, bv.get_size()))
+        }
+        PathConstraint::Not(inner) => {
+            assert_constraint(ctx, solver, inner, !negate);
+            return;
+        }
+    };
+    if negate {
+        solver.assert(&expr.not());
+    } else {
+        solver.assert(&expr);
+    }
+}
+
+/// Reads the 32-byte word CALLDATALOAD would push for `offset`, zero-padding past the
+/// end of `calldata` the same way the interpreter does.
+fn calldata_word(calldata: &[u8], offset: usize) -> [u8; 32] {
+    let mut word = [0u8; 32];
+    for (i, slot) in word.iter_mut().enumerate() {
+        if let Some(b) = calldata.get(offset + i) {
+            *slot = *b;
+        }
+    }
+    word
+}
+
+/// Builds the operand for a branch constraint: if `value` is the word a preceding
+/// CALLDATALOAD just produced, every byte is tied to its calldata offset so the solver
+/// is free to pick it; otherwise `value` is some already-concrete computation (a
+/// pushed constant, a hash, ...) and is recorded as-is.
+fn taint_operand(value: EVMU256, pending_load: Option<(usize, [u8; 32])>) -> Vec<SymbolicByte> {
+    if let Some((offset, word)) = pending_load {
+        if EVMU256::from_be_bytes(word) == value {
+            return (0..32).map(|i| SymbolicByte::Symbolic(offset + i)).collect();
+        }
+    }
+    value.to_be_bytes::<32>().into_iter().map(SymbolicByte::Concrete).collect()
+}
+
+fn collect_offsets(prefix: &[PathConstraint]) -> HashSet<usize> {
+    fn visit(c: &PathConstraint, out: &mut HashSet<usize>) {
+        match c {
+            PathConstraint::Eq(a, b) | PathConstraint::Lt(a, b) | PathConstraint::Gt(a, b) => {
+                for side in [a, b] {
+                    for byte in side {
+                        if let SymbolicByte::Symbolic(o) = byte {
+                            out.insert(*o);
+                        }
+                    }
+                }
+            }
+            PathConstraint::IsZero(a) => {
+                for byte in a {
+                    if let SymbolicByte::Symbolic(o) = byte {
+                        out.insert(*o);
+                    }
+                }
+            }
+            PathConstraint::Not(inner) => visit(inner, out),
+        }
+    }
+    let mut out = HashSet::new();
+    for c in prefix {
+        visit(c, &mut out);
+    }
+    out
+}
+
+/// Tries to satisfy `prefix` with the branch at `prefix[flip_at]` negated. On success,
+/// returns one concrete byte value per distinct symbolic offset referenced anywhere in
+/// `prefix`, which the caller splices back into a clone of the seed's calldata.
+pub fn solve_branch(prefix: &[PathConstraint], flip_at: usize) -> Option<Vec<(usize, u8)>> {
+    let cfg = Config::new();
+    let ctx = Context::new(&cfg);
+    let solver = Solver::new(&ctx);
+
+    for (idx, constraint) in prefix.iter().enumerate() {
+        assert_constraint(&ctx, &solver, constraint, idx == flip_at);
+    }
+
+    match solver.check() {
+        SatResult::Sat => {
+            let model = solver.get_model()?;
+            let mut offsets: Vec<usize> = collect_offsets(prefix).into_iter().collect();
+            offsets.sort_unstable();
+            let mut result = vec![];
+            for offset in offsets {
+                let var = BV::new_const(&ctx, format!("byte_{offset}"), 8);
+                let value = model.eval(&var, true)?.as_u64()? as u8;
+                result.push((offset, value));
+            }
+            Some(result)
+        }
+        // UNSAT or the solver timed out (see `Config::set_timeout_msec` at construction
+        // time, left at the z3 default here): either way, fall back to concrete fuzzing
+        // for this branch instead of blocking on it.
+        _ => None,
+    }
+}
+
+/// Concolic stage: tracks a seed's path condition opcode by opcode as it runs
+/// concretely, and for every branch not taken asks an SMT solver whether negating it
+/// (while keeping the rest of the path condition fixed) is satisfiable. Bounded per
+/// seed via `max_queue_per_seed` so one seed can't make the constraint queue explode,
+/// and caches path-condition prefixes already found unsat so identical guards aren't
+/// re-solved on the next run through the same code.
+///
+/// FOLLOW-UP, not yet done: nothing constructs a `ConcolicStage`, registers it in the
+/// live middleware stack, or calls `drain_pending()` after a seed finishes executing
+/// and splices its solved offsets into a cloned `EVMInput` via `add_input_to_corpus!`
+/// (the way `seed_signature_calldata`'s callers do in `corpus_initializer.rs`). That
+/// wiring belongs in the runtime fuzz loop that owns the executor and scheduler after
+/// each execution, which isn't part of this module; this type and its solver are
+/// ready to be driven from there, but are not live yet.
+pub struct ConcolicStage<VS, I, S> {
+    max_queue_per_seed: usize,
+    pending: VecDeque<Vec<(usize, u8)>>,
+    unsat_cache: HashSet<u64>,
+    current: PathCondition,
+    /// The calldata word (offset, 32-byte value) a CALLDATALOAD just pushed, valid only
+    /// for the first opcode after it that is not a PUSH -- see `taint_operand` below.
+    pending_load: Option<(usize, [u8; 32])>,
+    _phantom: PhantomData<(VS, I, S)>,
+}
+
+impl<VS, I, S> ConcolicStage<VS, I, S> {
+    pub fn new(max_queue_per_seed: usize) -> Self {
+        Self {
+            max_queue_per_seed,
+            pending: VecDeque::new(),
+            unsat_cache: HashSet::new(),
+            current: PathCondition::default(),
+            pending_load: None,
+            _phantom: PhantomData,
+        }
+    }
+
+    /// Records a concrete comparison the interpreter just took against symbolic
+    /// calldata, and immediately tries flipping it to queue a new candidate input.
+    pub fn record_and_try_flip(&mut self, constraint: PathConstraint) {
+        self.current.constraints.push(constraint);
+        if self.pending.len() >= self.max_queue_per_seed {
+            return;
+        }
+
+        let flip_at = self.current.constraints.len() - 1;
+        let prefix_hash = PathCondition::hash_prefix(&self.current.constraints[..=flip_at]);
+        if self.unsat_cache.contains(&prefix_hash) {
+            return;
+        }
+
+        match solve_branch(&self.current.constraints, flip_at) {
+            Some(assignment) => {
+                debug!("concolic stage: flipped branch {flip_at}, solved {} bytes", assignment.len());
+                self.pending.push_back(assignment);
+            }
+            None => {
+                self.unsat_cache.insert(prefix_hash);
+            }
+        }
+    }
+
+    /// Drains the branch assignments queued for the seed just finished, resetting the
+    /// path condition for the next one. The caller is expected to splice each solved
+    /// offset into a clone of the seed's `EVMInput.data` and push it with
+    /// `add_input_to_corpus!`, the same as `seed_signature_calldata` callers do.
+    pub fn drain_pending(&mut self) -> Vec<Vec<(usize, u8)>> {
+        self.current = PathCondition::default();
+        self.pending.drain(..).collect()
+    }
+}
+
+impl<VS, I, S, SC> Middleware<VS, I, S, SC> for ConcolicStage<VS, I, S>
+where
+    I: EVMInputT + 'static,
+{
+    unsafe fn on_step(&mut self, interp: &mut Interpreter, _host: &mut FuzzHost<VS, I, S, SC>, _s: &mut S) {
+        // Full taint tracking would need to follow symbolic bytes through arbitrary
+        // stack/memory arithmetic, which nothing in this tree threads through the
+        // interpreter yet. What's cheap and covers the dominant case (a selector or
+        // argument check done directly against a loaded word, e.g.
+        // `CALLDATALOAD(off); PUSH <k>; EQ`) is precomputing the word a CALLDATALOAD
+        // is about to push and recognizing it by value on the very next opcode.
+        let op = *interp.instruction_pointer;
+
+        if op == 0x35 {
+            let offset = interp.stack.peek(0).unwrap().to::<u64>() as usize;
+            self.pending_load = Some((offset, calldata_word(&interp.contract.input, offset)));
+            return;
+        }
+        // PUSH0..PUSH32 only bring the other operand in; keep the loaded word pending.
+        if (0x5f..=0x7f).contains(&op) {
+            return;
+        }
+        let pending_load = self.pending_load.take();
+
+        let constraint = match op {
+            0x14 => Some(PathConstraint::Eq(
+                taint_operand(interp.stack.peek(0).unwrap(), pending_load),
+                taint_operand(interp.stack.peek(1).unwrap(), pending_load),
+            )),
+            0x10 => Some(PathConstraint::Lt(
+                taint_operand(interp.stack.peek(0).unwrap(), pending_load),
+                taint_operand(interp.stack.peek(1).unwrap(), pending_load),
+            )),
+            0x11 => Some(PathConstraint::Gt(
+                taint_operand(interp.stack.peek(0).unwrap(), pending_load),
+                taint_operand(interp.stack.peek(1).unwrap(), pending_load),
+            )),
+            0x15 => Some(PathConstraint::IsZero(taint_operand(
+                interp.stack.peek(0).unwrap(),
+                pending_load,
+            ))),
+            _ => None,
+        };
+
+        // A constraint over two fully-concrete operands gives the solver nothing to
+        // pick, so only queue it once at least one byte actually traces to calldata.
+        if let Some(constraint) = constraint {
+            if !collect_offsets(std::slice::from_ref(&constraint)).is_empty() {
+                self.record_and_try_flip(constraint);
+            }
+        }
+    }
+
+    fn get_type(&self) -> MiddlewareType {
+        MiddlewareType::ConcolicStage
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn to_bv_concatenates_bytes_in_order() {
+        let cfg = Config::new();
+        let ctx = Context::new(&cfg);
+        let bytes = vec![SymbolicByte::Concrete(0x01), SymbolicByte::Concrete(0x02)];
+        let bv = to_bv(&ctx, &bytes);
+        assert_eq!(bv.get_size(), 16);
+
+        let solver = Solver::new(&ctx);
+        solver.assert(&bv._eq(&BV::from_u64(&ctx, 0x0102, 16)));
+        assert_eq!(solver.check(), SatResult::Sat);
+    }
+
+    #[test]
+    fn to_bv_introduces_one_free_variable_per_symbolic_offset() {
+        let cfg = Config::new();
+        let ctx = Context::new(&cfg);
+        // The same offset appearing twice must resolve to the same free variable.
+        let bytes = vec![SymbolicByte::Symbolic(3), SymbolicByte::Symbolic(3)];
+        let bv = to_bv(&ctx, &bytes);
+
+        let solver = Solver::new(&ctx);
+        solver.assert(&bv._eq(&BV::from_u64(&ctx, 0x0102, 16)));
+        // 0x01 != 0x02, so a value that ties both halves to the same `byte_3` variable
+        // is unsatisfiable.
+        assert_eq!(solver.check(), SatResult::Unsat);
+    }
+
+    #[test]
+    fn solve_branch_finds_assignment_for_equality() {
+        // Flipping the taken branch byte_0 != 0x2a asks for byte_0 == 0x2a.
+        let prefix = vec![PathConstraint::Not(Box::new(PathConstraint::Eq(
+            vec![SymbolicByte::Symbolic(0)],
+            vec![SymbolicByte::Concrete(0x2a)],
+        )))];
+        let assignment = solve_branch(&prefix, 0).expect("equality is satisfiable");
+        assert_eq!(assignment, vec![(0, 0x2a)]);
+    }
+
+    #[test]
+    fn solve_branch_flips_the_targeted_constraint() {
+        // byte_0 == 0x2a, negated, must find any value other than 0x2a.
+        let prefix = vec![PathConstraint::Eq(
+            vec![SymbolicByte::Symbolic(0)],
+            vec![SymbolicByte::Concrete(0x2a)],
+        )];
+        assert_eq!(prefix.len(), 1);
+        let assignment = solve_branch(&prefix, 0).expect("negated equality is satisfiable");
+        assert_ne!(assignment[0].1, 0x2a);
+    }
+
+    #[test]
+    fn solve_branch_rejects_contradictory_prefix() {
+        // Flipping byte_0 != 0x02 under byte_0 == 0x01 asks for both byte_0 == 0x01
+        // AND byte_0 == 0x02, which can never both hold.
+        let prefix = vec![
+            PathConstraint::Eq(vec![SymbolicByte::Symbolic(0)], vec![SymbolicByte::Concrete(0x01)]),
+            PathConstraint::Not(Box::new(PathConstraint::Eq(
+                vec![SymbolicByte::Symbolic(0)],
+                vec![SymbolicByte::Concrete(0x02)],
+            ))),
+        ];
+        assert!(solve_branch(&prefix, 1).is_none());
+    }
+
+    #[test]
+    fn taint_operand_recognizes_matching_calldataload_word() {
+        let mut word = [0u8; 32];
+        word[31] = 0x2a;
+        let value = EVMU256::from_be_bytes(word);
+
+        let bytes = taint_operand(value, Some((4, word)));
+        assert!(matches!(bytes[31], SymbolicByte::Symbolic(35)));
+        assert!(matches!(bytes[0], SymbolicByte::Symbolic(4)));
+    }
+
+    #[test]
+    fn taint_operand_falls_back_to_concrete_without_a_pending_load() {
+        let value = EVMU256::from(0x2au64);
+        let bytes = taint_operand(value, None);
+        assert!(matches!(bytes[31], SymbolicByte::Concrete(0x2a)));
+    }
+}
@@ -25,6 +25,7 @@ use libafl::{
 // impl_serdeany is used when `flashloan_v2` feature is not enabled
 #[allow(unused_imports)]
 use libafl_bolts::impl_serdeany;
+use retry::{delay::Fixed, retry_with_index, OperationResult};
 use revm_interpreter::Interpreter;
 use serde::{Deserialize, Serialize};
 use tracing::debug;
@@ -37,7 +38,10 @@ use crate::{
         contract_utils::ABIConfig,
         host::FuzzHost,
         input::{ConciseEVMInput, EVMInput, EVMInputT, EVMInputTy},
-        middlewares::middleware::{CallMiddlewareReturn::ReturnSuccess, Middleware, MiddlewareOp, MiddlewareType},
+        middlewares::{
+            call_trace::CallType,
+            middleware::{Middleware, MiddlewareOp, MiddlewareType},
+        },
         mutator::AccessPattern,
         onchain::{
             endpoints::{OnChainConfig, PriceOracle},
@@ -57,6 +61,27 @@ macro_rules! scale {
         EVMU512::from(1_000_000)
     };
 }
+
+thread_local! {
+    // EIP-1283 "original value": the value of a pair reserve / erc20 balance slot the
+    // first time it's written in the in-flight top-level transaction, captured before
+    // the SSTORE executes. Compared against the slot's final committed value once the
+    // transaction returns to depth 0, so a store that nets out to a no-op (or is undone
+    // by a reverted sub-call) doesn't trigger a redundant oracle recheck.
+    static RESERVE_ORIGINALS: RefCell<HashMap<(EVMAddress, EVMU256), EVMU256>> = RefCell::new(HashMap::new());
+    static BALANCE_ORIGINALS: RefCell<HashMap<(EVMAddress, EVMU256), EVMU256>> = RefCell::new(HashMap::new());
+    static CALL_DEPTH: RefCell<usize> = RefCell::new(0);
+}
+
+/// Splits a UniswapV2 pair's packed slot-8 value into `(reserve0, reserve1)`: the low
+/// 112 bits are `reserve0`, the next 112 `reserve1`, and the top 32 `blockTimestampLast`.
+fn unpack_reserves(slot: EVMU256) -> (EVMU256, EVMU256) {
+    let base = EVMU256::from(2).pow(EVMU256::from(112));
+    let reserve0 = slot % base;
+    let reserve1 = (slot / base) % base;
+    (reserve0, reserve1)
+}
+
 pub struct Flashloan<VS, I, S>
 where
     S: State + HasCaller<EVMAddress> + Debug + Clone + 'static,
@@ -70,10 +95,21 @@ where
     endpoint: Option<OnChainConfig>,
     erc20_address: HashSet<EVMAddress>,
     pair_address: HashSet<EVMAddress>,
+    /// Addresses that never run interpreted bytecode of their own (the standard
+    /// precompiles 0x01-0x09 by default, plus any the harness registers), so a CALL
+    /// into one is never treated as a real sub-call.
+    precompile_address: HashSet<EVMAddress>,
     pub unbound_tracker: HashMap<usize, HashSet<EVMAddress>>, // pc -> [address called]
     pub flashloan_oracle: Rc<RefCell<IERC20OracleFlashloan>>,
 }
 
+/// `ecrecover` through `blake2f`: the standard precompile range every EVM chain ships,
+/// used to seed [`Flashloan::precompile_address`] before any harness-specific
+/// precompiles are registered.
+fn standard_precompiles() -> HashSet<EVMAddress> {
+    (1u64..=9).map(EVMAddress::from_low_u64_be).collect()
+}
+
 impl<VS, I, S> Debug for Flashloan<VS, I, S>
 where
     S: State + HasCaller<EVMAddress> + Debug + Clone + 'static,
@@ -88,12 +124,85 @@ where
     }
 }
 
+/// A price-oracle lookup failure: a transient problem (RPC timeout, onchain backend
+/// corruption) that the caller should retry or back off on, as opposed to `Ok(None)`,
+/// which means the lookup succeeded and the token genuinely has no known price.
+#[derive(Clone, Debug)]
+pub enum OracleError {
+    Rpc(String),
+    Backend(String),
+}
+
+impl std::fmt::Display for OracleError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            OracleError::Rpc(msg) => write!(f, "RPC error fetching token price: {msg}"),
+            OracleError::Backend(msg) => {
+                write!(f, "oracle backend error fetching token price: {msg}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for OracleError {}
+
 #[derive(Clone, Debug)]
 pub struct DummyPriceOracle;
 
 impl PriceOracle for DummyPriceOracle {
-    fn fetch_token_price(&mut self, _token_address: EVMAddress) -> Option<(u32, u32)> {
-        Some((10000, 18))
+    fn fetch_token_price(
+        &mut self,
+        _token_address: EVMAddress,
+    ) -> Result<Option<(u32, u32)>, OracleError> {
+        Ok(Some((10000, 18)))
+    }
+}
+
+/// Queries an ordered list of sub-oracles and returns the first price found. Only
+/// answers `Ok(None)` ("this token has no price") once every source that didn't error
+/// has agreed on that; an `Err` is returned only when every source errored, so a single
+/// flaky source doesn't mask a price a healthier source would have found.
+pub struct FallbackPriceOracle {
+    sources: Vec<Box<dyn PriceOracle>>,
+}
+
+impl FallbackPriceOracle {
+    pub fn new(sources: Vec<Box<dyn PriceOracle>>) -> Self {
+        Self { sources }
+    }
+}
+
+impl Debug for FallbackPriceOracle {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("FallbackPriceOracle")
+            .field("sources", &self.sources.len())
+            .finish()
+    }
+}
+
+impl PriceOracle for FallbackPriceOracle {
+    fn fetch_token_price(
+        &mut self,
+        token_address: EVMAddress,
+    ) -> Result<Option<(u32, u32)>, OracleError> {
+        let total = self.sources.len();
+        let mut errors = 0;
+        let mut last_err = None;
+        for source in self.sources.iter_mut() {
+            match source.fetch_token_price(token_address) {
+                Ok(Some(price)) => return Ok(Some(price)),
+                Ok(None) => {}
+                Err(e) => {
+                    errors += 1;
+                    last_err = Some(e);
+                }
+            }
+        }
+        if total > 0 && errors == total {
+            Err(last_err.unwrap())
+        } else {
+            Ok(None)
+        }
     }
 }
 
@@ -138,7 +247,9 @@ where
     ) as Testcase<I>;
     tc.set_exec_time(Duration::from_secs(0));
     let idx = state.corpus_mut().add(tc).expect("failed to add");
-    scheduler.on_add(state, idx).expect("failed to call scheduler on_add");
+    scheduler
+        .on_add(state, idx)
+        .expect("failed to call scheduler on_add");
 }
 
 impl<VS, I, S> Flashloan<VS, I, S>
@@ -170,11 +281,18 @@ where
             endpoint,
             erc20_address: Default::default(),
             pair_address: Default::default(),
+            precompile_address: standard_precompiles(),
             unbound_tracker: Default::default(),
             flashloan_oracle,
         }
     }
 
+    /// Registers an additional address (e.g. a chain-specific precompile) whose calls
+    /// should never be attributed to `earned`/`owed` or queue an oracle recheck.
+    pub fn register_precompile(&mut self, addr: EVMAddress) {
+        self.precompile_address.insert(addr);
+    }
+
     #[allow(dead_code)]
     fn calculate_usd_value((eth_price, decimals): (u32, u32), amount: EVMU256) -> EVMU512 {
         let amount = if decimals > 18 {
@@ -187,22 +305,54 @@ where
     }
 
     #[allow(dead_code)]
-    fn calculate_usd_value_from_addr(&mut self, addr: EVMAddress, amount: EVMU256) -> Option<EVMU512> {
-        self.oracle
-            .fetch_token_price(addr)
-            .map(|price| Self::calculate_usd_value(price, amount))
+    fn calculate_usd_value_from_addr(
+        &mut self,
+        addr: EVMAddress,
+        amount: EVMU256,
+    ) -> Result<Option<EVMU512>, OracleError> {
+        Ok(self
+            .oracle
+            .fetch_token_price(addr)?
+            .map(|price| Self::calculate_usd_value(price, amount)))
     }
 
-    fn get_token_context(&mut self, addr: EVMAddress) -> Option<TokenContext> {
-        match &mut self.endpoint {
-            Some(endpoint) => {
-                Some(endpoint.fetch_uniswap_path_cached(addr).clone())
+    fn get_token_context(&mut self, addr: EVMAddress) -> Result<Option<TokenContext>, OracleError> {
+        let endpoint = match &mut self.endpoint {
+            Some(endpoint) => endpoint,
+            None => return Ok(None),
+        };
+
+        // `fetch_uniswap_path_cached` hands back a bare `TokenContext`, not a
+        // `Result`, so the only failure signal available here is its own
+        // `TokenContext::default()` sentinel (no weth address found), which a
+        // transient RPC timeout against the configured endpoint produces just as
+        // often as a genuine "this token has no uniswap path" does. Retry with
+        // backoff -- the same `retry` crate already used for the RPC calls path-finding
+        // makes -- before treating it as a real failure, so a flaky lookup doesn't
+        // permanently take a liquidatable token out of consideration.
+        let result = retry_with_index(Fixed::from_millis(200).take(3), |attempt| {
+            let ctx = endpoint.fetch_uniswap_path_cached(addr).clone();
+            if ctx.weth_address.is_zero() {
+                OperationResult::Retry(attempt)
+            } else {
+                OperationResult::Ok(ctx)
             }
-            None => None,
+        });
+
+        match result {
+            Ok(ctx) => Ok(Some(ctx)),
+            Err(_) => Err(OracleError::Rpc(format!(
+                "no uniswap path found for token {addr:?} after retrying"
+            ))),
         }
     }
 
-    pub fn on_contract_insertion(&mut self, addr: &EVMAddress, abi: &[ABIConfig], _state: &mut S) -> (bool, bool) {
+    pub fn on_contract_insertion(
+        &mut self,
+        addr: &EVMAddress,
+        abi: &[ABIConfig],
+        _state: &mut S,
+    ) -> (bool, bool) {
         // should not happen, just sanity check
         if self.known_addresses.contains(addr) {
             return (false, false);
@@ -218,7 +368,10 @@ where
         ];
 
         let abi_signatures_pair = vec!["skim".to_string(), "sync".to_string(), "swap".to_string()];
-        let abi_names = abi.iter().map(|x| x.function_name.clone()).collect::<HashSet<String>>();
+        let abi_names = abi
+            .iter()
+            .map(|x| x.function_name.clone())
+            .collect::<HashSet<String>>();
 
         let mut is_erc20 = false;
         let mut is_pair = false;
@@ -226,24 +379,29 @@ where
         {
             if abi_signatures_token.iter().all(|x| abi_names.contains(x)) {
                 match self.get_token_context(*addr) {
-                    Some(token_ctx) => {
+                    Ok(Some(token_ctx)) => {
                         let oracle = self.flashloan_oracle.deref().try_borrow_mut();
                         // avoid delegate call on token -> make oracle borrow multiple times
                         if oracle.is_ok() {
-                            oracle
-                                .unwrap()
-                                .register_token(*addr, token_ctx);
+                            oracle.unwrap().register_token(*addr, token_ctx);
                             self.erc20_address.insert(*addr);
                             is_erc20 = true;
                         } else {
                             debug!("Unable to liquidate token {:?}", addr);
                         }
                     }
-                    None => {
-                        debug!("Unable to liquidate token {:?}", addr);
+                    Ok(None) => {
+                        debug!(
+                            "No price context for token {:?}, skipping liquidation",
+                            addr
+                        );
+                    }
+                    Err(e) => {
+                        // Transient RPC/backend failure, distinct from the confirmed
+                        // "no price" of `Ok(None)` above.
+                        debug!("Unable to liquidate token {:?}: {}", addr, e);
                     }
                 }
-                
             }
         }
 
@@ -257,8 +415,12 @@ where
         (is_erc20, is_pair)
     }
 
-    pub fn on_pair_insertion<SC>(&mut self, host: &FuzzHost<VS, I, S, SC>, state: &mut S, pair: EVMAddress)
-    where
+    pub fn on_pair_insertion<SC>(
+        &mut self,
+        host: &FuzzHost<VS, I, S, SC>,
+        state: &mut S,
+        pair: EVMAddress,
+    ) where
         SC: Scheduler<State = S> + Clone,
     {
         let slots = host.find_static_call_read_slot(
@@ -286,22 +448,125 @@ where
     pub fn analyze_call(&self, input: &I, flashloan_data: &mut FlashloanData) {
         // if the txn is a transfer op, record it
         if input.get_txn_value().is_some() {
-            flashloan_data.owed += EVMU512::from(input.get_txn_value().unwrap()) * scale!();
+            flashloan_data.add_owed(EVMU512::from(input.get_txn_value().unwrap()) * scale!());
         }
         let addr = input.get_contract();
         // dont care if the call target is not erc20
         if self.erc20_address.contains(&addr) {
             // if the target is erc20 contract, then check the balance of the caller in the
             // oracle
-            flashloan_data.oracle_recheck_balance.insert(addr);
+            flashloan_data.mark_recheck_balance(addr);
         }
 
         if self.pair_address.contains(&addr) {
             // if the target is pair contract, then check the balance of the caller in the
             // oracle
-            flashloan_data.oracle_recheck_reserve.insert(addr);
+            flashloan_data.mark_recheck_reserve(addr);
         }
     }
+
+    /// Records `addr`'s current value at `slot` as its EIP-1283 "original value" for
+    /// the in-flight transaction, the first time this slot is touched. No-op on a
+    /// second write to the same slot, so the snapshot stays the pre-transaction value.
+    fn snapshot_original<SC>(
+        originals: &'static std::thread::LocalKey<RefCell<HashMap<(EVMAddress, EVMU256), EVMU256>>>,
+        host: &mut FuzzHost<VS, I, S, SC>,
+        addr: EVMAddress,
+        slot: EVMU256,
+    ) {
+        let current = host
+            .evmstate
+            .state
+            .get(&addr)
+            .and_then(|s| s.get(&slot))
+            .cloned()
+            .unwrap_or_default();
+        originals.with(|m| m.borrow_mut().entry((addr, slot)).or_insert(current));
+    }
+
+    /// Whether `addr` has any bytecode of its own to run. A CALL-family opcode only
+    /// ever opens a fresh `Interpreter` frame (and so only ever has a matching
+    /// RETURN/STOP/REVERT to checkpoint against) when its target has code; a plain EOA
+    /// -- exactly what a profit transfer to `s.has_caller(&call_target)` targets --
+    /// has none.
+    fn has_code<SC>(host: &mut FuzzHost<VS, I, S, SC>, addr: EVMAddress) -> bool {
+        host.code(addr).map(|(code, _)| !code.is_empty()).unwrap_or(false)
+    }
+
+    /// Pops one frame off `CALL_DEPTH` for a RETURN/STOP/REVERT (or abnormal halt) just
+    /// observed, and finalizes the transaction's storage diffs iff that frame was the
+    /// outermost one.
+    ///
+    /// The top-level call's own entry is never seen as a `CallType` opcode (nothing
+    /// calls it -- the executor starts it directly), so it's the only frame that exits
+    /// while `CALL_DEPTH` is already `0`: every *nested* call increments it on entry
+    /// and is the one responsible for decrementing it back on exit. Treating "decremented
+    /// down to 0" as the finish line (rather than "was already 0 before decrementing")
+    /// would fire on every nested call's own return, not just the transaction's true
+    /// end -- e.g. a top-level call that makes two sequential nested calls, the first
+    /// reverting a reserve slot and the second un-reverting it, would otherwise finalize
+    /// (and drain the originals snapshot) after the first nested call, losing the true
+    /// pre-transaction baseline before the second nested call's net-zero change is seen.
+    fn pop_call_depth_and_maybe_finalize<SC>(host: &mut FuzzHost<VS, I, S, SC>) {
+        let was_top_level_exit = CALL_DEPTH.with(|d| {
+            let mut d = d.borrow_mut();
+            if *d == 0 {
+                true
+            } else {
+                *d -= 1;
+                false
+            }
+        });
+        if was_top_level_exit {
+            Self::finalize_storage_diffs(host);
+        }
+    }
+
+    /// Called once the top-level transaction's call stack has fully unwound: compares
+    /// every reserve/balance slot touched this transaction against its snapshotted
+    /// original, flags `oracle_recheck_reserve`/`oracle_recheck_balance` (and records
+    /// the pre-transaction reserves) only where the net change is non-zero, then clears
+    /// the snapshots for the next transaction.
+    fn finalize_storage_diffs<SC>(host: &mut FuzzHost<VS, I, S, SC>) {
+        RESERVE_ORIGINALS.with(|m| {
+            for ((addr, slot), orig) in m.borrow_mut().drain() {
+                let final_val = host
+                    .evmstate
+                    .state
+                    .get(&addr)
+                    .and_then(|s| s.get(&slot))
+                    .cloned()
+                    .unwrap_or_default();
+                if final_val != orig {
+                    let (reserve0, reserve1) = unpack_reserves(orig);
+                    host.evmstate
+                        .flashloan_data
+                        .prev_reserves
+                        .insert(addr, (reserve0, reserve1));
+                    host.evmstate.flashloan_data.mark_recheck_reserve(addr);
+                    // Refresh the value dictionary with the reserve this transaction
+                    // actually settled on, so later mutations can target it directly
+                    // instead of hoping uniform random bytes land nearby.
+                    crate::evm::value_dictionary::push_u256(final_val);
+                }
+            }
+        });
+        BALANCE_ORIGINALS.with(|m| {
+            for ((addr, slot), orig) in m.borrow_mut().drain() {
+                let final_val = host
+                    .evmstate
+                    .state
+                    .get(&addr)
+                    .and_then(|s| s.get(&slot))
+                    .cloned()
+                    .unwrap_or_default();
+                if final_val != orig {
+                    host.evmstate.flashloan_data.mark_recheck_balance(addr);
+                    crate::evm::value_dictionary::push_u256(final_val);
+                }
+            }
+        });
+    }
 }
 
 impl<VS, I, S, SC> Middleware<VS, I, S, SC> for Flashloan<VS, I, S>
@@ -320,8 +585,12 @@ where
     VS: VMStateT,
     SC: Scheduler<State = S> + Clone,
 {
-    unsafe fn on_step(&mut self, interp: &mut Interpreter, host: &mut FuzzHost<VS, I, S, SC>, s: &mut S)
-    where
+    unsafe fn on_step(
+        &mut self,
+        interp: &mut Interpreter,
+        host: &mut FuzzHost<VS, I, S, SC>,
+        s: &mut S,
+    ) where
         S: HasCaller<EVMAddress>,
     {
         // if simply static call, we dont care
@@ -329,18 +598,66 @@ where
         //     return;
         // }
 
-        match *interp.instruction_pointer {
+        // An abnormal halt (out-of-gas, INVALID, stack under/overflow, ...) never
+        // executes a RETURN/STOP/REVERT opcode of its own, so the opcode match below
+        // would never see it and the checkpoint pushed for this frame would stay on
+        // the stack forever, permanently desyncing it (and `CALL_DEPTH`) from the real
+        // call stack for the rest of this input's execution. Treat it exactly like an
+        // explicit REVERT: unwind this frame's checkpoint and undo everything it
+        // logged, same as the `0xfd` arm below.
+        if interp.instruction_result.is_error() {
+            host.evmstate.flashloan_data.revert();
+            Self::pop_call_depth_and_maybe_finalize(host);
+            return;
+        }
+
+        let op = *interp.instruction_pointer;
+
+        // CALL/CALLCODE/DELEGATECALL/STATICCALL into a precompile -- or into a plain
+        // EOA, which is exactly what a profit transfer to `s.has_caller(&call_target)`
+        // below targets -- never pushes an interpreted bytecode frame of its own (the
+        // precompile runs natively, the way a symbolic EVM like rhoevm has to
+        // special-case its ecrecover handler; the EOA has no code at all), so it will
+        // never hit a matching RETURN/STOP/REVERT below. Checkpointing one would desync
+        // `CALL_DEPTH` from the real call stack and hand its `earned`/recheck
+        // bookkeeping to whatever unrelated frame returns next, so both cases are
+        // detected up front and never checkpointed.
+        let precompile_target = matches!(op, 0xf1 | 0xf2 | 0xf4 | 0xfa)
+            .then(|| convert_u256_to_h160(interp.stack.peek(1).unwrap()))
+            .filter(|addr| self.precompile_address.contains(addr) || !Self::has_code(host, *addr));
+
+        // Checkpoint on every non-precompile call-family opcode and commit/rollback at
+        // the matching RETURN/STOP/REVERT, the same "entry at CALL, exit approximated
+        // at the next RETURN/REVERT/STOP" strategy `CallTraceRecorder` uses, so a
+        // REVERTed sub-call can't leave behind the `earned`/recheck bookkeeping it
+        // would have made: attribution is only ever kept once the matching frame
+        // commits, i.e. actually succeeds.
+        if CallType::from_opcode(op).is_some() {
+            if precompile_target.is_none() {
+                host.evmstate.flashloan_data.checkpoint();
+                CALL_DEPTH.with(|d| *d.borrow_mut() += 1);
+            }
+        } else {
+            match op {
+                0xf3 | 0x00 => host.evmstate.flashloan_data.commit(),
+                0xfd => host.evmstate.flashloan_data.revert(),
+                _ => {}
+            }
+            if matches!(op, 0xf3 | 0x00 | 0xfd) {
+                Self::pop_call_depth_and_maybe_finalize(host);
+            }
+        }
+
+        match op {
             // detect whether it mutates token balance
             0xf1 | 0xfa => {}
             0x55 => {
-                if self.pair_address.contains(&interp.contract.address) {
-                    let key = interp.stack.peek(0).unwrap();
-                    if key == EVMU256::from(8) {
-                        host.evmstate
-                            .flashloan_data
-                            .oracle_recheck_reserve
-                            .insert(interp.contract.address);
-                    }
+                let key = interp.stack.peek(0).unwrap();
+                let addr = interp.contract.address;
+                if self.pair_address.contains(&addr) && key == EVMU256::from(8) {
+                    Self::snapshot_original(&RESERVE_ORIGINALS, host, addr, key);
+                } else if self.erc20_address.contains(&addr) {
+                    Self::snapshot_original(&BALANCE_ORIGINALS, host, addr, key);
                 }
                 return;
             }
@@ -349,6 +666,14 @@ where
             }
         };
 
+        // Value sent to, or an ERC20-shaped call made against, a precompile is never
+        // liquidation profit or a balance-mutating call: bail before either is recorded.
+        // A plain EOA target (also in `precompile_target`) falls through, so a profit
+        // transfer to a caller is still credited.
+        if precompile_target.map_or(false, |addr| self.precompile_address.contains(&addr)) {
+            return;
+        }
+
         let value_transfer = match *interp.instruction_pointer {
             0xf1 | 0xf2 => interp.stack.peek(2).unwrap(),
             _ => EVMU256::ZERO,
@@ -358,12 +683,16 @@ where
         let call_target: EVMAddress = convert_u256_to_h160(interp.stack.peek(1).unwrap());
 
         if value_transfer > EVMU256::ZERO && s.has_caller(&call_target) {
-            host.evmstate.flashloan_data.earned += EVMU512::from(value_transfer) * scale!();
+            host.evmstate
+                .flashloan_data
+                .add_earned(EVMU512::from(value_transfer) * scale!());
         }
 
         let call_target: EVMAddress = convert_u256_to_h160(interp.stack.peek(1).unwrap());
         if self.erc20_address.contains(&call_target) {
-            host.evmstate.flashloan_data.oracle_recheck_balance.insert(call_target);
+            host.evmstate
+                .flashloan_data
+                .mark_recheck_balance(call_target);
         }
     }
 
@@ -372,6 +701,42 @@ where
     }
 }
 
+/// One journaled mutation of [`FlashloanData`], recording enough to undo it: the
+/// inverse delta for an accumulator, or whether a set/map entry was new at this depth
+/// (an update to an already-present entry must not be undone, or a parent frame's
+/// recheck would be wrongly dropped).
+#[derive(Clone, Debug)]
+enum JournalEntry {
+    Earned(EVMU512),
+    Owed(EVMU512),
+    RecheckReserve(EVMAddress),
+    RecheckBalance(EVMAddress),
+    UnliquidatedToken(EVMAddress, Option<EVMU256>),
+}
+
+impl JournalEntry {
+    fn undo(self, data: &mut FlashloanData) {
+        match self {
+            JournalEntry::Earned(delta) => data.earned -= delta,
+            JournalEntry::Owed(delta) => data.owed -= delta,
+            JournalEntry::RecheckReserve(addr) => {
+                data.oracle_recheck_reserve.remove(&addr);
+            }
+            JournalEntry::RecheckBalance(addr) => {
+                data.oracle_recheck_balance.remove(&addr);
+            }
+            JournalEntry::UnliquidatedToken(addr, prev) => match prev {
+                Some(v) => {
+                    data.unliquidated_tokens.insert(addr, v);
+                }
+                None => {
+                    data.unliquidated_tokens.remove(&addr);
+                }
+            },
+        }
+    }
+}
+
 #[derive(Clone, Debug, Serialize, Deserialize, Default)]
 pub struct FlashloanData {
     pub oracle_recheck_reserve: HashSet<EVMAddress>,
@@ -381,6 +746,14 @@ pub struct FlashloanData {
     pub prev_reserves: HashMap<EVMAddress, (EVMU256, EVMU256)>,
     pub unliquidated_tokens: HashMap<EVMAddress, EVMU256>,
     pub extra_info: String,
+
+    // EIP-2929/EIP-1283-style checkpoint journal: `checkpoints` holds the journal
+    // length at each call-frame entry; a REVERT pops back to the top one and undoes
+    // every entry logged since, a successful return just pops the marker and keeps them.
+    #[serde(skip)]
+    journal: Vec<JournalEntry>,
+    #[serde(skip)]
+    checkpoints: Vec<usize>,
 }
 
 impl FlashloanData {
@@ -393,6 +766,63 @@ impl FlashloanData {
             prev_reserves: Default::default(),
             unliquidated_tokens: Default::default(),
             extra_info: Default::default(),
+            journal: Vec::new(),
+            checkpoints: Vec::new(),
+        }
+    }
+
+    /// Marks entry into a new call frame; entries logged after this call undo back to
+    /// here on `revert`.
+    pub fn checkpoint(&mut self) {
+        self.checkpoints.push(self.journal.len());
+    }
+
+    /// Call frame returned successfully: keep every change logged since the matching
+    /// `checkpoint`, merging it into its parent frame.
+    pub fn commit(&mut self) {
+        self.checkpoints.pop();
+    }
+
+    /// Call frame REVERTed (or ran out of gas): pop journal entries back to the
+    /// matching `checkpoint` and undo each one.
+    pub fn revert(&mut self) {
+        let mark = self.checkpoints.pop().unwrap_or(0);
+        while self.journal.len() > mark {
+            let entry = self.journal.pop().expect("checked by loop condition");
+            entry.undo(self);
+        }
+    }
+
+    fn log(&mut self, entry: JournalEntry) {
+        if !self.checkpoints.is_empty() {
+            self.journal.push(entry);
         }
     }
+
+    pub fn add_earned(&mut self, delta: EVMU512) {
+        self.earned += delta;
+        self.log(JournalEntry::Earned(delta));
+    }
+
+    pub fn add_owed(&mut self, delta: EVMU512) {
+        self.owed += delta;
+        self.log(JournalEntry::Owed(delta));
+    }
+
+    pub fn mark_recheck_reserve(&mut self, addr: EVMAddress) {
+        if self.oracle_recheck_reserve.insert(addr) {
+            self.log(JournalEntry::RecheckReserve(addr));
+        }
+    }
+
+    pub fn mark_recheck_balance(&mut self, addr: EVMAddress) {
+        if self.oracle_recheck_balance.insert(addr) {
+            self.log(JournalEntry::RecheckBalance(addr));
+        }
+    }
+
+    pub fn set_unliquidated_token(&mut self, addr: EVMAddress, amount: EVMU256) {
+        let prev = self.unliquidated_tokens.insert(addr, amount);
+        self.log(JournalEntry::UnliquidatedToken(addr, prev));
+    }
 }
@@ -15,10 +15,11 @@ use revm_primitives::Bytecode;
 use serde::{de::DeserializeOwned, Serialize};
 use tracing::debug;
 
-use super::{endpoints::PairData, ChainConfig};
+use super::{endpoints::PairData, precompiles::execute_precompile, ChainConfig};
 use crate::{
     evm::{
         types::{EVMAddress, EVMFuzzState, EVMU256},
+        value_dictionary::{push_address, push_u256},
         vm::{EVMExecutor, MEM_LIMIT},
     },
     generic_vm::vm_state::VMStateT,
@@ -35,13 +36,19 @@ const WETH: &str = "0x4200000000000000000000000000000000000006";
 pub struct OffChainConfig {
     /// Preset v2 pairs
     pub v2_pairs: HashSet<EVMAddress>,
+    /// Preset v3 pools
+    pub v3_pools: HashSet<EVMAddress>,
 
-    // token -> pair_data
+    // token -> pair_data (v2)
     pair_cache: HashMap<EVMAddress, Vec<PairData>>,
+    // token -> pair_data (v3)
+    v3_pair_cache: HashMap<EVMAddress, Vec<PairData>>,
     // pair -> reserves
     reserves_cache: HashMap<EVMAddress, (EVMU256, EVMU256)>,
     // (pair,token) -> balance
     balance_cache: HashMap<(EVMAddress, EVMAddress), EVMU256>,
+    // v3 pool -> fee tier
+    v3_fee_cache: HashMap<EVMAddress, u32>,
 }
 
 impl OffChainConfig {
@@ -53,7 +60,17 @@ impl OffChainConfig {
         }
     }
 
-    pub fn initialize<VS, CI, SC>(&mut self, state: &mut EVMFuzzState, vm: &mut EVMExecutor<VS, CI, SC>) -> Result<()>
+    /// Adds Uniswap V3 pools to be indexed alongside `v2_pairs` when `initialize` runs.
+    pub fn with_v3_pools(mut self, v3_pools: &[EVMAddress]) -> Self {
+        self.v3_pools = v3_pools.iter().cloned().collect();
+        self
+    }
+
+    pub fn initialize<VS, CI, SC>(
+        &mut self,
+        state: &mut EVMFuzzState,
+        vm: &mut EVMExecutor<VS, CI, SC>,
+    ) -> Result<()>
     where
         VS: VMStateT + Default + 'static,
         CI: Serialize + DeserializeOwned + Debug + Clone + ConciseSerde + 'static,
@@ -64,6 +81,22 @@ impl OffChainConfig {
             self.build_cache(pair, state, vm)?;
         }
 
+        let v3_pools = self.v3_pools.clone();
+        for pool in v3_pools {
+            self.build_cache_v3(pool, state, vm)?;
+        }
+
+        push_address(EVMAddress::from_str(WETH).unwrap());
+        // Flatten every storage slot touched while building the cache into the value
+        // dictionary too, so words the contracts themselves consider meaningful (not
+        // just the ones this struct explicitly tracks) become candidate arguments.
+        for slots in vm.host.evmstate.state.values() {
+            for (slot, value) in slots {
+                push_u256(*slot);
+                push_u256(*value);
+            }
+        }
+
         Ok(())
     }
 
@@ -91,7 +124,13 @@ impl OffChainConfig {
             .host
             .code(token0)
             .ok_or_else(|| anyhow!("Token0 {:?} code not found", token0))?;
-        let res = self.call(self.decimals_input(), token0_code.clone(), token0, state, vm)?;
+        let res = self.call(
+            self.decimals_input(),
+            token0_code.clone(),
+            token0,
+            state,
+            vm,
+        )?;
         let decimals_0 = res[31] as u32;
 
         // token1
@@ -101,18 +140,42 @@ impl OffChainConfig {
             .host
             .code(token1)
             .ok_or_else(|| anyhow!("Token1 {:?} code not found", token1))?;
-        let res = self.call(self.decimals_input(), token1_code.clone(), token1, state, vm)?;
+        let res = self.call(
+            self.decimals_input(),
+            token1_code.clone(),
+            token1,
+            state,
+            vm,
+        )?;
         let decimals_1 = res[31] as u32;
 
         // reserves
-        let res = self.call(self.get_reserves_input(), pair_code.clone(), pair, state, vm)?;
+        let res = self.call(
+            self.get_reserves_input(),
+            pair_code.clone(),
+            pair,
+            state,
+            vm,
+        )?;
         let reserves0 = EVMU256::try_from_be_slice(&res[..32]).unwrap_or_default();
         let reserves1 = EVMU256::try_from_be_slice(&res[32..64]).unwrap_or_default();
 
         // balances
-        let res = self.call(self.balance_of_input(pair), token0_code.clone(), token0, state, vm)?;
+        let res = self.call(
+            self.balance_of_input(pair),
+            token0_code.clone(),
+            token0,
+            state,
+            vm,
+        )?;
         let balance0 = EVMU256::try_from_be_slice(res.to_vec().as_slice()).unwrap_or_default();
-        let res = self.call(self.balance_of_input(pair), token1_code.clone(), token1, state, vm)?;
+        let res = self.call(
+            self.balance_of_input(pair),
+            token1_code.clone(),
+            token1,
+            state,
+            vm,
+        )?;
         let balance1 = EVMU256::try_from_be_slice(res.to_vec().as_slice()).unwrap_or_default();
 
         let pair_data = PairData {
@@ -127,6 +190,16 @@ impl OffChainConfig {
         };
         debug!("Pair data: {:?}", pair_data);
 
+        push_address(pair);
+        push_address(token0);
+        push_address(token1);
+        push_u256(reserves0);
+        push_u256(reserves1);
+        push_u256(EVMU256::from(decimals_0));
+        push_u256(EVMU256::from(decimals_1));
+        push_u256(balance0);
+        push_u256(balance1);
+
         // build cache
         self.build_pair_cache(token0, pair_data.clone());
         self.build_pair_cache(token1, pair_data);
@@ -157,6 +230,140 @@ impl OffChainConfig {
         self.pair_cache.entry(token).or_default().push(pair);
     }
 
+    fn build_cache_v3<VS, CI, SC>(
+        &mut self,
+        pool: EVMAddress,
+        state: &mut EVMFuzzState,
+        vm: &mut EVMExecutor<VS, CI, SC>,
+    ) -> Result<()>
+    where
+        VS: VMStateT + Default + 'static,
+        CI: Serialize + DeserializeOwned + Debug + Clone + ConciseSerde + 'static,
+        SC: Scheduler<State = EVMFuzzState> + Clone + 'static,
+    {
+        debug!("Building cache for v3 pool: {:?}", pool);
+        let (pool_code, _) = vm
+            .host
+            .code(pool)
+            .ok_or_else(|| anyhow!("Pool {:?} code not found", pool))?;
+
+        // token0
+        let res = self.call(self.token0_input(), pool_code.clone(), pool, state, vm)?;
+        let token0 = EVMAddress::from_slice(&res[12..32]);
+        let (token0_code, _) = vm
+            .host
+            .code(token0)
+            .ok_or_else(|| anyhow!("Token0 {:?} code not found", token0))?;
+        let res = self.call(
+            self.decimals_input(),
+            token0_code.clone(),
+            token0,
+            state,
+            vm,
+        )?;
+        let decimals_0 = res[31] as u32;
+
+        // token1
+        let res = self.call(self.token1_input(), pool_code.clone(), pool, state, vm)?;
+        let token1 = EVMAddress::from_slice(&res[12..32]);
+        let (token1_code, _) = vm
+            .host
+            .code(token1)
+            .ok_or_else(|| anyhow!("Token1 {:?} code not found", token1))?;
+        let res = self.call(
+            self.decimals_input(),
+            token1_code.clone(),
+            token1,
+            state,
+            vm,
+        )?;
+        let decimals_1 = res[31] as u32;
+
+        // slot0(): sqrtPriceX96 is the first word, tick the second
+        let res = self.call(self.slot0_input(), pool_code.clone(), pool, state, vm)?;
+        let sqrt_price_x96 = EVMU256::try_from_be_slice(&res[..32]).unwrap_or_default();
+        let tick = i32::from_be_bytes(res[60..64].try_into().unwrap());
+
+        // liquidity()
+        let res = self.call(self.liquidity_input(), pool_code.clone(), pool, state, vm)?;
+        let liquidity = EVMU256::try_from_be_slice(&res[..32]).unwrap_or_default();
+
+        // fee()
+        let res = self.call(self.fee_input(), pool_code.clone(), pool, state, vm)?;
+        let fee = u32::from_be_bytes(res[28..32].try_into().unwrap());
+
+        let (reserve0, reserve1) = virtual_v3_reserves(sqrt_price_x96, liquidity);
+
+        let pair_data = PairData {
+            pair: format!("{:?}", pool),
+            token0: format!("{:?}", token0),
+            token1: format!("{:?}", token1),
+            decimals_0,
+            decimals_1,
+            initial_reserves_0: reserve0,
+            initial_reserves_1: reserve1,
+            ..Default::default()
+        };
+        debug!(
+            "V3 pair data: {:?}, tick: {}, fee: {}",
+            pair_data, tick, fee
+        );
+
+        push_address(pool);
+        push_address(token0);
+        push_address(token1);
+        push_u256(sqrt_price_x96);
+        push_u256(liquidity);
+        push_u256(reserve0);
+        push_u256(reserve1);
+        push_u256(EVMU256::from(decimals_0));
+        push_u256(EVMU256::from(decimals_1));
+
+        // build cache
+        self.build_pair_cache_v3(token0, pair_data.clone(), sqrt_price_x96, liquidity);
+        self.build_pair_cache_v3(token1, pair_data, sqrt_price_x96, liquidity);
+        self.reserves_cache.insert(pool, (reserve0, reserve1));
+        self.v3_fee_cache.insert(pool, fee);
+
+        Ok(())
+    }
+
+    fn build_pair_cache_v3(
+        &mut self,
+        token: EVMAddress,
+        mut pair: PairData,
+        sqrt_price_x96: EVMU256,
+        liquidity: EVMU256,
+    ) {
+        let in_token = format!("{:?}", token);
+        pair.in_ = if in_token == pair.token0 { 0 } else { 1 };
+        pair.next = if in_token == pair.token0 {
+            pair.token1.clone()
+        } else {
+            in_token.clone()
+        };
+        pair.in_token = in_token.clone();
+        pair.interface = "uniswapv3".to_string();
+        pair.src_exact = "uniswapv3_eth".to_string();
+        // `fetch_uniswap_path`'s "v3" arm re-derives reserves from these two fields
+        // rather than from `initial_reserves_0/1`, so they have to be populated here
+        // for a hop built from this cache to price the same way a live-subgraph v3 hop
+        // does.
+        pair.sqrt_price_x96 = hex::encode(sqrt_price_x96.to_be_bytes::<32>());
+        pair.liquidity = hex::encode(liquidity.to_be_bytes::<32>());
+        pair.src = if self.get_pegged_token().values().contains(&in_token) {
+            "pegged".to_string()
+        } else {
+            // Tagged "v3", not "lp": a v3 pool has no raw reserve slot to refetch, so
+            // it must flow through `fetch_uniswap_path`/`add_reserve_info`'s dedicated
+            // "v3" handling (virtual reserves, liquidity-gated depth) instead of the
+            // v2 path's `fetch_reserve` call.
+            "v3".to_string()
+        };
+
+        self.v3_pair_cache.entry(token).or_default().push(pair);
+    }
+
     fn call<VS, CI, SC>(
         &self,
         input: Bytes,
@@ -170,6 +377,14 @@ impl OffChainConfig {
         CI: Serialize + DeserializeOwned + Debug + Clone + ConciseSerde + 'static,
         SC: Scheduler<State = EVMFuzzState> + Clone + 'static,
     {
+        // Calls to 0x01-0x05 (ecrecover, sha256, ripemd160, identity, modexp) run
+        // natively: a pair/token touching `ecrecover` (e.g. an ERC-2612 `permit`) or
+        // one of the others would otherwise reach bytecode execution at an address
+        // with no code and fail. 0x06-0x09 still take the bytecode path.
+        if let Some(output) = execute_precompile(target, &input) {
+            return Ok(output);
+        }
+
         let call = Contract::new_with_context_analyzed(
             input,
             code,
@@ -223,12 +438,45 @@ impl OffChainConfig {
         input.extend_from_slice(&addr.0); // addr
         Bytes::from(input)
     }
+
+    // slot0()
+    #[inline]
+    fn slot0_input(&self) -> Bytes {
+        Bytes::from(hex!("3850c7bd").to_vec())
+    }
+
+    // liquidity()
+    #[inline]
+    fn liquidity_input(&self) -> Bytes {
+        Bytes::from(hex!("1a686502").to_vec())
+    }
+
+    // fee()
+    #[inline]
+    fn fee_input(&self) -> Bytes {
+        Bytes::from(hex!("ddca3f43").to_vec())
+    }
+}
+
+/// Derives constant-product-equivalent reserves from Uniswap V3's `sqrtPriceX96` and
+/// in-range liquidity `L`, the same way `fetch_uniswap_path` does for on-chain V3 hops,
+/// so `fetch_reserve` has something to hand back for a pool that has no raw reserve slot.
+fn virtual_v3_reserves(sqrt_price_x96: EVMU256, liquidity: EVMU256) -> (EVMU256, EVMU256) {
+    if sqrt_price_x96.is_zero() {
+        return (EVMU256::ZERO, EVMU256::ZERO);
+    }
+    let q96 = EVMU256::from(2).pow(EVMU256::from(96));
+    let reserve0 = liquidity.saturating_mul(q96) / sqrt_price_x96;
+    let reserve1 = liquidity.saturating_mul(sqrt_price_x96) / q96;
+    (reserve0, reserve1)
 }
 
 impl ChainConfig for OffChainConfig {
     fn get_pair(&mut self, token: &str, _is_pegged: bool) -> Vec<PairData> {
         let token = EVMAddress::from_str(token).unwrap();
-        self.pair_cache.get(&token).cloned().unwrap_or_default()
+        let mut pairs = self.pair_cache.get(&token).cloned().unwrap_or_default();
+        pairs.extend(self.v3_pair_cache.get(&token).cloned().unwrap_or_default());
+        pairs
     }
 
     fn fetch_reserve(&self, pair: &str) -> Option<(String, String)> {
@@ -241,12 +489,15 @@ impl ChainConfig for OffChainConfig {
         unreachable!()
     }
 
-    fn get_v3_fee(&mut self, _address: EVMAddress) -> u32 {
-        0
+    fn get_v3_fee(&mut self, address: EVMAddress) -> u32 {
+        self.v3_fee_cache.get(&address).cloned().unwrap_or_default()
     }
 
     fn get_token_balance(&mut self, token: EVMAddress, address: EVMAddress) -> EVMU256 {
-        self.balance_cache.get(&(address, token)).cloned().unwrap_or_default()
+        self.balance_cache
+            .get(&(address, token))
+            .cloned()
+            .unwrap_or_default()
     }
 
     fn get_weth(&self) -> String {
@@ -266,7 +517,9 @@ mod tests {
 
     use super::*;
     use crate::{
-        evm::{host::FuzzHost, input::ConciseEVMInput, types::generate_random_address, vm::EVMState},
+        evm::{
+            host::FuzzHost, input::ConciseEVMInput, types::generate_random_address, vm::EVMState,
+        },
         generic_vm::vm_executor::GenericVM,
         logger,
     };
@@ -372,15 +625,21 @@ mod tests {
         // slot 7: token1
         slots.insert(EVMU256::from(7), EVMU256::from_be_slice(token1.as_slice()));
         // slot 8: blockTimestampLast + reserve1 + reserve0
-        let slot8 =
-            EVMU256::from_str_radix("660e130b000000000000000041062620fcfd00000000049f9bc137cd08508bb0", 16).unwrap();
+        let slot8 = EVMU256::from_str_radix(
+            "660e130b000000000000000041062620fcfd00000000049f9bc137cd08508bb0",
+            16,
+        )
+        .unwrap();
         slots.insert(EVMU256::from(8), slot8);
 
         // Initialize token0
         let slots = vm.host.evmstate.state.get_mut(token0).unwrap();
         // balanceOf pair
-        let slot =
-            EVMU256::from_str_radix("aced72359d8708e95d2112ba70e71fa267967a5588d15e7c78c1904e0debe410", 16).unwrap();
+        let slot = EVMU256::from_str_radix(
+            "aced72359d8708e95d2112ba70e71fa267967a5588d15e7c78c1904e0debe410",
+            16,
+        )
+        .unwrap();
         slots.insert(slot, EVMU256::from(21519275363657114356534u128));
         // slot 2: decimals
         slots.insert(EVMU256::from(2), EVMU256::from(18));
@@ -388,8 +647,11 @@ mod tests {
         // Initialize token1
         let slots = vm.host.evmstate.state.get_mut(token1).unwrap();
         // balanceOf pair
-        let slot =
-            EVMU256::from_str_radix("45b1147656da4d940c556082f0e09e91e3d046c1c84468f8ead64d8fdc1c749a", 16).unwrap();
+        let slot = EVMU256::from_str_radix(
+            "45b1147656da4d940c556082f0e09e91e3d046c1c84468f8ead64d8fdc1c749a",
+            16,
+        )
+        .unwrap();
         slots.insert(slot, EVMU256::from(72553743663529u128));
         // slot 9: decimals
         slots.insert(EVMU256::from(9), EVMU256::from(6));
@@ -0,0 +1,179 @@
+use std::{cell::RefCell, collections::HashMap, fs, path::PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use super::endpoints::PairData;
+
+/// Distinguishes the two things a [`PairIndex`] caches, so a token address and a pair
+/// address never collide in the same key space.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash, Serialize, Deserialize)]
+enum PairIndexKind {
+    Pairs,
+    Reserves,
+}
+
+/// Key identifying a cached lookup. Reserves (and the routes built from them) are
+/// block-dependent, so the block number is part of the key: an entry cached while
+/// replaying block N must never answer a query pinned to block M.
+#[derive(Clone, Debug, Eq, PartialEq, Hash, Serialize, Deserialize)]
+struct PairIndexKey {
+    chain: String,
+    block_number: u64,
+    kind: PairIndexKind,
+    subject: String,
+}
+
+/// Pluggable backing store for [`PairIndex`]. Implementations persist (or don't)
+/// discovered pair/reserve data so repeated fuzzing campaigns, or multiple tokens that
+/// share a hop, don't re-query the chain for data already seen at this block.
+pub trait PairIndexStore {
+    fn get_pairs(&self, key: &str) -> Option<Vec<PairData>>;
+    fn put_pairs(&mut self, key: &str, pairs: Vec<PairData>);
+    fn get_reserve(&self, key: &str) -> Option<(String, String)>;
+    fn put_reserve(&mut self, key: &str, reserves: (String, String));
+}
+
+#[derive(Default)]
+struct InMemoryPairIndexStore {
+    pairs: HashMap<String, Vec<PairData>>,
+    reserves: HashMap<String, (String, String)>,
+}
+
+impl PairIndexStore for InMemoryPairIndexStore {
+    fn get_pairs(&self, key: &str) -> Option<Vec<PairData>> {
+        self.pairs.get(key).cloned()
+    }
+
+    fn put_pairs(&mut self, key: &str, pairs: Vec<PairData>) {
+        self.pairs.insert(key.to_string(), pairs);
+    }
+
+    fn get_reserve(&self, key: &str) -> Option<(String, String)> {
+        self.reserves.get(key).cloned()
+    }
+
+    fn put_reserve(&mut self, key: &str, reserves: (String, String)) {
+        self.reserves.insert(key.to_string(), reserves);
+    }
+}
+
+/// On-disk store mirroring the in-memory one to a JSON file under a cache directory,
+/// one file per `(chain, block_number)`. This is deliberately the simplest backend
+/// that makes offline replay of a campaign possible; a sled/sqlite-backed
+/// `PairIndexStore` can be dropped in later without touching `PairIndex` itself.
+struct FilePairIndexStore {
+    path: PathBuf,
+    memory: InMemoryPairIndexStore,
+}
+
+#[derive(Default, Serialize, Deserialize)]
+struct FilePairIndexContents {
+    pairs: HashMap<String, Vec<PairData>>,
+    reserves: HashMap<String, (String, String)>,
+}
+
+impl FilePairIndexStore {
+    fn new(cache_dir: PathBuf, chain: &str, block_number: u64) -> Self {
+        let _ = fs::create_dir_all(&cache_dir);
+        let path = cache_dir.join(format!("{chain}-{block_number}.json"));
+        let memory = Self::load(&path).unwrap_or_default();
+        Self { path, memory }
+    }
+
+    fn load(path: &PathBuf) -> Option<InMemoryPairIndexStore> {
+        let data = fs::read_to_string(path).ok()?;
+        let contents: FilePairIndexContents = serde_json::from_str(&data).ok()?;
+        Some(InMemoryPairIndexStore {
+            pairs: contents.pairs,
+            reserves: contents.reserves,
+        })
+    }
+
+    fn flush(&self) {
+        let contents = FilePairIndexContents {
+            pairs: self.memory.pairs.clone(),
+            reserves: self.memory.reserves.clone(),
+        };
+        if let Ok(data) = serde_json::to_string(&contents) {
+            let _ = fs::write(&self.path, data);
+        }
+    }
+}
+
+impl PairIndexStore for FilePairIndexStore {
+    fn get_pairs(&self, key: &str) -> Option<Vec<PairData>> {
+        self.memory.get_pairs(key)
+    }
+
+    fn put_pairs(&mut self, key: &str, pairs: Vec<PairData>) {
+        self.memory.put_pairs(key, pairs);
+        self.flush();
+    }
+
+    fn get_reserve(&self, key: &str) -> Option<(String, String)> {
+        self.memory.get_reserve(key)
+    }
+
+    fn put_reserve(&mut self, key: &str, reserves: (String, String)) {
+        self.memory.put_reserve(key, reserves);
+        self.flush();
+    }
+}
+
+/// Block-scoped pre-index of discovered pair/reserve data, modeled on graph-node's
+/// block-scoped entity store: chain data is materialized once per `(chain,
+/// block_number)` and served to every path-finding call that shares it, instead of
+/// every token re-issuing the same `get_pair`/`fetch_reserve` RPCs.
+pub struct PairIndex {
+    chain: String,
+    block_number: u64,
+    store: RefCell<Box<dyn PairIndexStore>>,
+}
+
+impl PairIndex {
+    pub fn in_memory(chain: String, block_number: u64) -> Self {
+        Self {
+            chain,
+            block_number,
+            store: RefCell::new(Box::new(InMemoryPairIndexStore::default())),
+        }
+    }
+
+    pub fn on_disk(chain: String, block_number: u64, cache_dir: PathBuf) -> Self {
+        let store = FilePairIndexStore::new(cache_dir, &chain, block_number);
+        Self {
+            chain,
+            block_number,
+            store: RefCell::new(Box::new(store)),
+        }
+    }
+
+    fn key(&self, kind: PairIndexKind, subject: &str) -> PairIndexKey {
+        PairIndexKey {
+            chain: self.chain.clone(),
+            block_number: self.block_number,
+            kind,
+            subject: subject.to_lowercase(),
+        }
+    }
+
+    pub fn get_pairs(&self, token: &str) -> Option<Vec<PairData>> {
+        let key = self.key(PairIndexKind::Pairs, token);
+        self.store.borrow().get_pairs(&key.subject)
+    }
+
+    pub fn put_pairs(&self, token: &str, pairs: Vec<PairData>) {
+        let key = self.key(PairIndexKind::Pairs, token);
+        self.store.borrow_mut().put_pairs(&key.subject, pairs);
+    }
+
+    pub fn get_reserve(&self, pair: &str) -> Option<(String, String)> {
+        let key = self.key(PairIndexKind::Reserves, pair);
+        self.store.borrow().get_reserve(&key.subject)
+    }
+
+    pub fn put_reserve(&self, pair: &str, reserves: (String, String)) {
+        let key = self.key(PairIndexKind::Reserves, pair);
+        self.store.borrow_mut().put_reserve(&key.subject, reserves);
+    }
+}
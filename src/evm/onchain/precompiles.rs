@@ -0,0 +1,192 @@
+use bytes::Bytes;
+use num_bigint::BigUint;
+use ripemd::Ripemd160;
+use secp256k1::{
+    ecdsa::{RecoverableSignature, RecoveryId},
+    Message,
+    Secp256k1,
+};
+use sha2::{Digest as Sha2Digest, Sha256};
+use sha3::{Digest, Keccak256};
+
+use crate::evm::types::EVMAddress;
+
+/// Runs one of the standard precompiled contracts `0x01`-`0x05` (ecrecover, sha256,
+/// ripemd160, identity, modexp) natively instead of interpreting bytecode, the same way
+/// a real EVM intercepts calls to these addresses before ever looking up code at them.
+/// Returns `None` for any other address, so the caller falls back to its normal
+/// bytecode path; that includes the precompiles `0x06`-`0x09` (bn256 add/mul/pairing,
+/// blake2f), which are not implemented here.
+///
+/// FOLLOW-UP, not yet done: only `OffChainConfig::call` (off-chain cache construction)
+/// calls this. Making a live fuzzing run resolve a CALL into 0x01-0x09 the same way
+/// requires this same check in the main interpreter's `Host::call` dispatch, which
+/// decides what code (if any) a CALL target runs *before* any `Middleware::on_step`
+/// ever sees the callee's frame -- a `Middleware` only observes opcodes within a frame
+/// revm has already decided to spin up, so it cannot retroactively substitute one.
+/// That dispatch lives outside this module's files; land the wiring there when that
+/// code is in scope, don't fake it from a middleware.
+pub fn execute_precompile(address: EVMAddress, input: &[u8]) -> Option<Bytes> {
+    let id = address.0;
+    if id[..19] != [0u8; 19] {
+        return None;
+    }
+    match id[19] {
+        0x01 => Some(ecrecover(input)),
+        0x02 => Some(sha256(input)),
+        0x03 => Some(ripemd160(input)),
+        0x04 => Some(identity(input)),
+        0x05 => Some(modexp(input)),
+        _ => None,
+    }
+}
+
+/// `ecrecover(hash, v, r, s)`: input is `hash[32] || v[32] || r[32] || s[32]`, output is
+/// the recovered address left-padded to 32 bytes. Any malformed input (bad `v`, failed
+/// recovery) yields empty output rather than a revert, matching real EVM semantics.
+fn ecrecover(input: &[u8]) -> Bytes {
+    let mut padded = [0u8; 128];
+    let len = input.len().min(128);
+    padded[..len].copy_from_slice(&input[..len]);
+
+    let hash = &padded[0..32];
+    let v_bytes = &padded[32..64];
+    let r = &padded[64..96];
+    let s = &padded[96..128];
+
+    // v's high 31 bytes must be zero and the low byte must be 27 or 28.
+    if v_bytes[..31] != [0u8; 31] || (v_bytes[31] != 27 && v_bytes[31] != 28) {
+        return Bytes::new();
+    }
+    let recovery_id = match RecoveryId::from_i32((v_bytes[31] - 27) as i32) {
+        Ok(id) => id,
+        Err(_) => return Bytes::new(),
+    };
+
+    let mut compact = [0u8; 64];
+    compact[..32].copy_from_slice(r);
+    compact[32..].copy_from_slice(s);
+    let signature = match RecoverableSignature::from_compact(&compact, recovery_id) {
+        Ok(sig) => sig,
+        Err(_) => return Bytes::new(),
+    };
+    let message = match Message::from_slice(hash) {
+        Ok(m) => m,
+        Err(_) => return Bytes::new(),
+    };
+
+    let secp = Secp256k1::verification_only();
+    let public_key = match secp.recover_ecdsa(&message, &signature) {
+        Ok(pk) => pk,
+        Err(_) => return Bytes::new(),
+    };
+
+    let uncompressed = public_key.serialize_uncompressed();
+    let digest = Keccak256::digest(&uncompressed[1..]);
+    let mut out = [0u8; 32];
+    out[12..].copy_from_slice(&digest[12..]);
+    Bytes::from(out.to_vec())
+}
+
+fn sha256(input: &[u8]) -> Bytes {
+    Bytes::from(Sha256::digest(input).to_vec())
+}
+
+fn ripemd160(input: &[u8]) -> Bytes {
+    let digest = Ripemd160::digest(input);
+    let mut out = [0u8; 32];
+    out[12..].copy_from_slice(&digest);
+    Bytes::from(out.to_vec())
+}
+
+fn identity(input: &[u8]) -> Bytes {
+    Bytes::from(input.to_vec())
+}
+
+/// `modexp(base, exponent, modulus)`: the first 96 bytes give the byte lengths of
+/// each operand, the rest is the operands themselves back to back.
+fn modexp(input: &[u8]) -> Bytes {
+    let read_len = |offset: usize| -> usize {
+        let mut buf = [0u8; 32];
+        for (i, byte) in buf.iter_mut().enumerate() {
+            if let Some(v) = input.get(offset + i) {
+                *byte = *v;
+            }
+        }
+        read_length_field(&buf)
+    };
+
+    let base_len = read_len(0);
+    let exp_len = read_len(32);
+    let mod_len = read_len(64);
+
+    if mod_len == 0 {
+        return Bytes::new();
+    }
+
+    let data_start = 96;
+    let read_operand = |offset: usize, len: usize| -> BigUint {
+        // `len` is already clamped by `read_length_field`, but re-clamp defensively so
+        // this allocation can never grow past `MAX_MODEXP_OPERAND_LEN` even if a future
+        // caller feeds `read_operand` a length from somewhere else.
+        let len = len.min(MAX_MODEXP_OPERAND_LEN);
+        let mut bytes = vec![0u8; len];
+        for (i, byte) in bytes.iter_mut().enumerate() {
+            if let Some(v) = input.get(data_start + offset + i) {
+                *byte = *v;
+            }
+        }
+        BigUint::from_bytes_be(&bytes)
+    };
+
+    let base = read_operand(0, base_len);
+    let exponent = read_operand(base_len, exp_len);
+    let modulus = read_operand(base_len + exp_len, mod_len);
+
+    let result = if modulus == BigUint::from(0u32) {
+        BigUint::from(0u32)
+    } else {
+        base.modpow(&exponent, &modulus)
+    };
+
+    let mut out = result.to_bytes_be();
+    if out.len() < mod_len {
+        let mut padded = vec![0u8; mod_len - out.len()];
+        padded.append(&mut out);
+        out = padded;
+    } else if out.len() > mod_len {
+        out = out[out.len() - mod_len..].to_vec();
+    }
+    Bytes::from(out)
+}
+
+/// Whether `address` falls in the standard precompile range (`0x01`-`0x09`) every EVM
+/// chain reserves, regardless of whether [`execute_precompile`] actually implements it.
+/// Call-depth/checkpoint bookkeeping that tracks "does this CALL target open an
+/// interpreted frame of its own" needs this broader check; `execute_precompile`'s own
+/// range only covers what it implements.
+pub fn is_precompile(address: EVMAddress) -> bool {
+    let id = address.0;
+    id[..19] == [0u8; 19] && matches!(id[19], 0x01..=0x09)
+}
+
+/// Largest operand `modexp` will allocate for, regardless of what a crafted length
+/// header claims. Real EVM gas costs already make an operand anywhere near this size
+/// prohibitively expensive; the cap exists purely to keep a malformed/adversarial
+/// length field from turning into a multi-gigabyte (or `usize::MAX`) allocation.
+const MAX_MODEXP_OPERAND_LEN: usize = 4096;
+
+/// Reads a big-endian 32-byte length field. A nonzero byte anywhere in the top 24
+/// bytes makes the encoded length nonsensical as an allocation size, so it's clamped
+/// to `MAX_MODEXP_OPERAND_LEN` rather than returned as the literal (up to
+/// `usize::MAX`) value, which would panic/OOM the `vec![0u8; len]` allocations in
+/// `modexp`.
+fn read_length_field(buf: &[u8; 32]) -> usize {
+    let nonzero_prefix = buf[..24].iter().any(|b| *b != 0);
+    if nonzero_prefix {
+        return MAX_MODEXP_OPERAND_LEN;
+    }
+    let mut out = [0u8; 8];
+    out.copy_from_slice(&buf[24..]);
+    (u64::from_be_bytes(out) as usize).min(MAX_MODEXP_OPERAND_LEN)
+}
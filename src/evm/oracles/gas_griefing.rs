@@ -0,0 +1,171 @@
+use std::{
+    cell::RefCell,
+    collections::{hash_map::DefaultHasher, HashMap, HashSet},
+    hash::{Hash, Hasher},
+};
+
+use bytes::Bytes;
+use revm_primitives::Bytecode;
+use tracing::debug;
+
+use crate::{
+    evm::{
+        corpus_initializer::ABIMap,
+        input::{ConciseEVMInput, EVMInput},
+        middlewares::storage_journal::{last_storage_journal, selector_history},
+        oracle::EVMBugResult,
+        oracles::GAS_GRIEFING_BUG_IDX,
+        types::{EVMAddress, EVMFuzzState, EVMOracleCtx, ProjectSourceMapTy, EVMU256},
+        vm::EVMState,
+    },
+    oracle::{Oracle, OracleCtx},
+    state::HasExecutionResult,
+};
+
+thread_local! {
+    // Selectors already reported, so a gas-griefing finding is only surfaced once per
+    // function instead of once per fuzz iteration that still shows growth.
+    static REPORTED: RefCell<HashSet<[u8; 4]>> = RefCell::new(HashSet::new());
+}
+
+/// Minimum number of distinct calldata sizes needed before growth can be judged
+/// anything more than noise.
+const MIN_SAMPLES: usize = 4;
+
+/// How closely cold-slot-touch growth has to track calldata-length growth, as a ratio
+/// of the two slopes measured between the smallest and largest sampled calldata size,
+/// before it's treated as "grows roughly linearly" rather than coincidence.
+const LINEARITY_TOLERANCE: f64 = 0.5;
+
+/// Looks for roughly-linear growth of cold-slot touches against calldata length across
+/// `samples`. Returns the observed slope (extra cold slots touched per extra calldata
+/// byte) when the growth looks attacker-controlled, `None` otherwise.
+fn linear_growth(samples: &[(usize, usize)]) -> Option<f64> {
+    let mut distinct_lens: HashMap<usize, usize> = HashMap::new();
+    for (len, cold) in samples {
+        distinct_lens.insert(*len, *cold);
+    }
+    if distinct_lens.len() < MIN_SAMPLES {
+        return None;
+    }
+
+    let mut points: Vec<(usize, usize)> = distinct_lens.into_iter().collect();
+    points.sort_by_key(|(len, _)| *len);
+
+    let (min_len, min_cold) = *points.first()?;
+    let (max_len, max_cold) = *points.last()?;
+    if max_len <= min_len || max_cold <= min_cold {
+        return None;
+    }
+    let overall_slope = (max_cold - min_cold) as f64 / (max_len - min_len) as f64;
+    if overall_slope <= 0.0 {
+        return None;
+    }
+
+    // Every consecutive pair should grow in the same direction and at a slope within
+    // tolerance of the overall one; a single outlier (e.g. an early-return branch)
+    // shouldn't by itself disprove linear growth, but most of the curve should track.
+    let mut consistent = 0;
+    for pair in points.windows(2) {
+        let (len_a, cold_a) = pair[0];
+        let (len_b, cold_b) = pair[1];
+        if len_b <= len_a {
+            continue;
+        }
+        let slope = (cold_b as f64 - cold_a as f64) / (len_b - len_a) as f64;
+        if slope >= 0.0 && (slope - overall_slope).abs() <= overall_slope * LINEARITY_TOLERANCE + 1.0 {
+            consistent += 1;
+        }
+    }
+    if consistent * 2 >= points.len() - 1 {
+        Some(overall_slope)
+    } else {
+        None
+    }
+}
+
+pub struct GasGriefingOracle {
+    address_to_name: HashMap<EVMAddress, String>,
+}
+
+impl GasGriefingOracle {
+    pub fn new(address_to_name: HashMap<EVMAddress, String>) -> Self {
+        Self { address_to_name }
+    }
+}
+
+impl
+    Oracle<EVMState, EVMAddress, Bytecode, Bytes, EVMAddress, EVMU256, Vec<u8>, EVMInput, EVMFuzzState, ConciseEVMInput>
+    for GasGriefingOracle
+{
+    fn transition(&self, _ctx: &mut EVMOracleCtx<'_>, _stage: u64) -> u64 {
+        0
+    }
+
+    fn oracle(
+        &self,
+        ctx: &mut OracleCtx<
+            EVMState,
+            EVMAddress,
+            Bytecode,
+            Bytes,
+            EVMAddress,
+            EVMU256,
+            Vec<u8>,
+            EVMInput,
+            EVMFuzzState,
+            ConciseEVMInput,
+        >,
+        _stage: u64,
+    ) -> Vec<u64> {
+        let Some(journal) = last_storage_journal() else {
+            return vec![];
+        };
+
+        if REPORTED.with(|r| r.borrow().contains(&journal.selector)) {
+            return vec![];
+        }
+
+        let history = selector_history(journal.selector);
+        let Some(slope) = linear_growth(&history) else {
+            return vec![];
+        };
+
+        REPORTED.with(|r| {
+            r.borrow_mut().insert(journal.selector);
+        });
+
+        let mut hasher = DefaultHasher::new();
+        journal.selector.hash(&mut hasher);
+        let real_bug_idx = hasher.finish() << (8 + GAS_GRIEFING_BUG_IDX);
+
+        let name = self.address_to_name.get(&journal.target).cloned().unwrap_or_else(|| format!("{:?}", journal.target));
+        let function_name = ctx
+            .fuzz_state
+            .metadata_map()
+            .get::<ABIMap>()
+            .and_then(|abi_map| abi_map.get(&journal.selector))
+            .map(|abi| abi.function_name.clone())
+            .unwrap_or_else(|| format!("0x{}", hex::encode(journal.selector)));
+
+        debug!("gas-griefing candidate: {name}::{function_name} touched slots growing ~{slope:.2} per calldata byte");
+
+        let description = format!(
+            "{name}::{function_name} touches a number of storage slots that grows roughly linearly with calldata \
+             size (~{slope:.2} cold slots per extra byte), suggesting an unbounded loop over attacker-controlled \
+             data that can be pushed past the block gas limit"
+        );
+
+        EVMBugResult::new(
+            "GasGriefing".to_string(),
+            real_bug_idx,
+            description,
+            ConciseEVMInput::from_input(ctx.input, ctx.fuzz_state.get_execution_result()),
+            None,
+            Some(name),
+        )
+        .push_to_output();
+
+        vec![real_bug_idx]
+    }
+}
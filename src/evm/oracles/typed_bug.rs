@@ -7,11 +7,14 @@ use bytes::Bytes;
 use itertools::Itertools;
 use libafl::state::HasMetadata;
 use revm_primitives::Bytecode;
+use tracing::debug;
 
 use crate::{
     evm::{
         blaz::builder::{ArtifactInfoMetadata, BuildJobResult},
+        corpus_initializer::ABIMap,
         input::{ConciseEVMInput, EVMInput},
+        middlewares::call_trace::{decode_node, last_call_trace, render_trace},
         oracle::EVMBugResult,
         oracles::TYPED_BUG_BUG_IDX,
         types::{EVMAddress, EVMFuzzState, EVMOracleCtx, ProjectSourceMapTy, EVMU256},
@@ -81,10 +84,29 @@ impl
                         &self.sourcemap,
                         *pc,
                     );
+                    // Ship a readable reproduction trace of the whole internal call tree
+                    // alongside the bare PC, decoding selectors/addresses via the ABI map
+                    // and address labels built during corpus initialization.
+                    let description = match (
+                        last_call_trace(),
+                        ctx.fuzz_state.metadata_map().get::<ABIMap>(),
+                    ) {
+                        (Some(trace), Some(abi_map)) => {
+                            let decoded = decode_node(&trace, abi_map, &self.address_to_name);
+                            debug!("call trace for bug {:?}:\n{}", bug_id, render_trace(&decoded));
+                            format!(
+                                "Invariant {:?} violated\ncall trace:\n{}",
+                                bug_id,
+                                render_trace(&decoded)
+                            )
+                        }
+                        _ => format!("Invariant {:?} violated", bug_id),
+                    };
+
                     EVMBugResult::new(
                         "Bug".to_string(),
                         real_bug_idx,
-                        format!("Invariant {:?} violated", bug_id,),
+                        description,
                         ConciseEVMInput::from_input(ctx.input, ctx.fuzz_state.get_execution_result()),
                         srcmap,
                         Some(name.clone()),
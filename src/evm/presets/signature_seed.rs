@@ -0,0 +1,146 @@
+use secp256k1::{ecdsa::RecoverableSignature, Message, Secp256k1, SecretKey};
+use sha3::{Digest, Keccak256};
+
+use crate::evm::{contract_utils::ABIConfig, types::EVMAddress};
+
+/// A small, fixed set of signer keypairs seeded calls can forge valid `(v, r, s)`
+/// signatures with. These are well-known test private keys (not meant to hold real
+/// value); their addresses should be registered as callers the same way
+/// `setup_default_callers` registers its own fixed addresses, so that
+/// `ecrecover(digest, v, r, s) == caller` holds for a call made by that caller.
+pub fn known_signers() -> Vec<(EVMAddress, SecretKey)> {
+    [
+        "0000000000000000000000000000000000000000000000000000000000000001",
+        "0000000000000000000000000000000000000000000000000000000000000002",
+    ]
+    .iter()
+    .filter_map(|hex_key| {
+        let bytes = hex::decode(hex_key).ok()?;
+        let secret_key = SecretKey::from_slice(&bytes).ok()?;
+        let address = address_of(&secret_key);
+        Some((address, secret_key))
+    })
+    .collect()
+}
+
+fn address_of(secret_key: &SecretKey) -> EVMAddress {
+    let secp = Secp256k1::new();
+    let public_key = secp256k1::PublicKey::from_secret_key(&secp, secret_key);
+    let uncompressed = public_key.serialize_uncompressed();
+    // drop the 0x04 prefix, keccak the remaining 64 bytes, keep the low 20
+    let hash = Keccak256::digest(&uncompressed[1..]);
+    EVMAddress::from_slice(&hash[12..])
+}
+
+pub fn signer_for(signers: &[(EVMAddress, SecretKey)], caller: EVMAddress) -> Option<SecretKey> {
+    signers.iter().find(|(addr, _)| *addr == caller).map(|(_, key)| *key)
+}
+
+/// Signs `digest` with `secret_key`, returning `(v, r, s)` in the layout `ecrecover`
+/// and Solidity's `permit`-style functions expect: `v` is 27 or 28, `r`/`s` are the
+/// compact 32-byte signature halves.
+pub fn sign_digest(secret_key: &SecretKey, digest: &[u8; 32]) -> (u8, [u8; 32], [u8; 32]) {
+    let secp = Secp256k1::signing_only();
+    let message = Message::from_slice(digest).expect("digest is 32 bytes");
+    let sig: RecoverableSignature = secp.sign_ecdsa_recoverable(&message, secret_key);
+    let (recovery_id, compact) = sig.serialize_compact();
+    let mut r = [0u8; 32];
+    let mut s = [0u8; 32];
+    r.copy_from_slice(&compact[..32]);
+    s.copy_from_slice(&compact[32..]);
+    (27 + recovery_id.to_i32() as u8, r, s)
+}
+
+/// `keccak256("\x19\x01" || domain_separator || struct_hash)`, the EIP-712 typed-data
+/// digest, used when the target exposes a `DOMAIN_SEPARATOR()` getter.
+pub fn eip712_digest(domain_separator: [u8; 32], struct_hash: [u8; 32]) -> [u8; 32] {
+    let mut hasher = Keccak256::new();
+    hasher.update([0x19, 0x01]);
+    hasher.update(domain_separator);
+    hasher.update(struct_hash);
+    hasher.finalize().into()
+}
+
+/// Whether an ABI parameter list looks like it ends in an ECDSA signature, and how
+/// it's laid out: the standard `(uint8 v, bytes32 r, bytes32 s)` triple used by
+/// `permit`/meta-tx functions, or a single packed 65-byte `bytes` signature.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum SignatureShape {
+    VrsTriple,
+    Packed65,
+}
+
+/// Best-effort heuristic over the declared Solidity type list: `ABIConfig` doesn't
+/// carry parameter names, so this only looks at the trailing types.
+pub fn detect_signature_shape(abi: &ABIConfig) -> Option<SignatureShape> {
+    let sig = abi.abi.to_lowercase();
+    if sig.ends_with("uint8,bytes32,bytes32)") {
+        Some(SignatureShape::VrsTriple)
+    } else if sig.ends_with(",bytes)") || sig == "(bytes)" {
+        Some(SignatureShape::Packed65)
+    } else {
+        None
+    }
+}
+
+/// Splices a freshly forged `(v, r, s)` (or packed 65-byte signature) into the tail of
+/// already-ABI-encoded `calldata`, replacing whatever placeholder bytes the default
+/// seeding put there. `calldata` is the full `selector || encoded args` buffer.
+pub fn splice_signature(calldata: &mut [u8], shape: SignatureShape, v: u8, r: [u8; 32], s: [u8; 32]) {
+    match shape {
+        SignatureShape::VrsTriple => {
+            let len = calldata.len();
+            if len < 96 {
+                return;
+            }
+            calldata[len - 96..len - 64].fill(0);
+            calldata[len - 65] = v;
+            calldata[len - 64..len - 32].copy_from_slice(&r);
+            calldata[len - 32..].copy_from_slice(&s);
+        }
+        SignatureShape::Packed65 => {
+            // A dynamic `bytes` parameter is ABI-encoded as its length word followed
+            // by its data right-padded to a 32-byte boundary, so a 65-byte packed
+            // signature occupies the *last 96* bytes of calldata, not the last 65:
+            // `r(32) || s(32) || v(1) || zero-pad(31)`. Writing into the literal last
+            // 65 bytes instead lands `v` 31 bytes short of where `ecrecover` reads it.
+            let len = calldata.len();
+            if len < 96 {
+                return;
+            }
+            calldata[len - 96..len - 64].copy_from_slice(&r);
+            calldata[len - 64..len - 32].copy_from_slice(&s);
+            calldata[len - 32] = v;
+            calldata[len - 31..].fill(0);
+        }
+    }
+}
+
+/// Forges a valid signature for `raw_calldata` (the full `selector || encoded args`
+/// buffer) and splices it into the signature tail detected via `abi`, if `caller` is
+/// one of `signers`. The digest is a plain keccak256 of the calldata preceding the
+/// signature; callers with access to the target's `DOMAIN_SEPARATOR()` should prefer
+/// [`eip712_digest`] instead and sign that.
+///
+/// Returns `None` when `abi` doesn't look signature-gated, or `caller` isn't a known
+/// signer.
+pub fn seed_signature_calldata(
+    abi: &ABIConfig,
+    raw_calldata: &[u8],
+    caller: EVMAddress,
+    signers: &[(EVMAddress, SecretKey)],
+) -> Option<Vec<u8>> {
+    let shape = detect_signature_shape(abi)?;
+    let secret_key = signer_for(signers, caller)?;
+
+    let prefix_len = match shape {
+        SignatureShape::VrsTriple => raw_calldata.len().saturating_sub(96),
+        SignatureShape::Packed65 => raw_calldata.len().saturating_sub(96),
+    };
+    let digest: [u8; 32] = Keccak256::digest(&raw_calldata[..prefix_len]).into();
+    let (v, r, s) = sign_digest(&secret_key, &digest);
+
+    let mut seeded = raw_calldata.to_vec();
+    splice_signature(&mut seeded, shape, v, r, s);
+    Some(seeded)
+}
@@ -11,6 +11,7 @@ use std::{
     time::Duration,
 };
 
+use anyhow::Result;
 use itertools::Itertools;
 use reqwest::header::HeaderMap;
 use retry::{delay::Fixed, retry_with_index, OperationResult};
@@ -21,7 +22,10 @@ use tracing::{debug, error, info, warn};
 
 use super::{get_uniswap_info, PairContext, PathContext, TokenContext, UniswapProvider};
 use crate::evm::{
-    onchain::endpoints::{Chain, OnChainConfig, PairData},
+    onchain::{
+        endpoints::{Chain, OnChainConfig, PairData},
+        pair_index::PairIndex,
+    },
     types::{EVMAddress, EVMU256},
 };
 
@@ -35,7 +39,92 @@ pub struct BasicInfo {
     is_weth: bool,
 }
 
-const MAX_HOPS: u32 = 2; // Assuming the value of MAX_HOPS
+const MAX_HOPS: u32 = 2; // Assuming the value of MAX_HOPS, used as PathFinderConfig's default
+
+/// Runtime-configurable knobs for path-finding: the max-hop count, the pegged/base
+/// token tables, and the DEX providers to route through. Loadable from a TOML/JSON
+/// file (or installed directly via [`set_path_finder_config`], e.g. from a CLI flag)
+/// so adding a new chain or AMM is a config change rather than a recompile. Any field
+/// left at its default falls back to the built-in tables in this module.
+#[derive(Clone, Debug, Default, Deserialize)]
+pub struct PathFinderConfig {
+    pub max_hops: Option<u32>,
+    /// network -> (symbol -> address), merged over (and overriding) the built-in table.
+    #[serde(default)]
+    pub pegged_tokens: HashMap<String, HashMap<String, String>>,
+    /// network -> symbol of that network's base/WETH-equivalent token.
+    #[serde(default)]
+    pub base_token_symbol: HashMap<String, String>,
+    /// network -> additional DEX provider/src_exact tags to route through.
+    #[serde(default)]
+    pub providers: HashMap<String, Vec<String>>,
+}
+
+impl PathFinderConfig {
+    pub fn max_hops(&self) -> u32 {
+        self.max_hops.unwrap_or(MAX_HOPS)
+    }
+
+    /// Loads a config from a `.toml` or `.json` file, picked by extension.
+    pub fn load(path: &str) -> Result<Self> {
+        let data = std::fs::read_to_string(path)?;
+        if path.ends_with(".json") {
+            Ok(serde_json::from_str(&data)?)
+        } else {
+            Ok(toml::from_str(&data)?)
+        }
+    }
+}
+
+thread_local! {
+    // One `PairIndex` per `(chain, block_number)`, shared by every token looked up
+    // during this process so that tokens sharing a hop only pay for it once.
+    static PAIR_INDEXES: RefCell<HashMap<(String, u64), Rc<PairIndex>>> = RefCell::new(HashMap::new());
+    static PATH_FINDER_CONFIG: RefCell<Option<Rc<PathFinderConfig>>> = RefCell::new(None);
+}
+
+/// Installs the `PathFinderConfig` used by every path-finding call in this process
+/// from then on (e.g. wired up from a CLI flag). If never called, the config is
+/// loaded lazily from `ITYFUZZ_PATH_FINDER_CONFIG` on first use, falling back to the
+/// built-in defaults.
+pub fn set_path_finder_config(config: PathFinderConfig) {
+    PATH_FINDER_CONFIG.with(|c| *c.borrow_mut() = Some(Rc::new(config)));
+}
+
+fn path_finder_config() -> Rc<PathFinderConfig> {
+    PATH_FINDER_CONFIG.with(|c| {
+        if let Some(cfg) = c.borrow().as_ref() {
+            return cfg.clone();
+        }
+        let cfg = Rc::new(
+            env::var("ITYFUZZ_PATH_FINDER_CONFIG")
+                .ok()
+                .and_then(|path| PathFinderConfig::load(&path).ok())
+                .unwrap_or_default(),
+        );
+        *c.borrow_mut() = Some(cfg.clone());
+        cfg
+    })
+}
+
+/// Returns the [`PairIndex`] for `onchain`'s `(chain, block_number)`, creating it on
+/// first use. Set `ITYFUZZ_PAIR_INDEX_CACHE_DIR` to back it with an on-disk cache so a
+/// campaign can be replayed offline against the same pinned block; otherwise the index
+/// only lives for this process.
+fn pair_index_for(onchain: &OnChainConfig) -> Rc<PairIndex> {
+    let key = (onchain.chain_name.clone(), onchain.block_number);
+    PAIR_INDEXES.with(|indexes| {
+        if let Some(idx) = indexes.borrow().get(&key) {
+            return idx.clone();
+        }
+        let idx = Rc::new(match env::var("ITYFUZZ_PAIR_INDEX_CACHE_DIR") {
+            Ok(dir) => PairIndex::on_disk(key.0.clone(), key.1, std::path::PathBuf::from(dir)),
+            Err(_) => PairIndex::in_memory(key.0.clone(), key.1),
+        });
+        indexes.borrow_mut().insert(key, idx.clone());
+        idx
+    })
+}
 
 pub fn fetch_uniswap_path(onchain: &mut OnChainConfig, token_address: EVMAddress) -> TokenContext {
     let token = format!("{:?}", token_address);
@@ -57,6 +146,32 @@ pub fn fetch_uniswap_path(onchain: &mut OnChainConfig, token_address: EVMAddress
             let mut path_parsed: PathContext = Default::default();
             pairs.iter().for_each(|pair| {
                 match pair.src.as_str() {
+                    "v3" => {
+                        // V3 price comes from sqrtPriceX96/liquidity rather than raw reserves;
+                        // derive virtual x*y=k reserves so this hop prices the same way a v2
+                        // hop does downstream, instead of threading a second swap model through
+                        // every consumer of `PairContext`. This is a constant-product
+                        // approximation of the current tick's price, not real tick-aware swap
+                        // math -- see `virtual_v3_reserves`'s doc comment for why.
+                        let sqrt_price_x96 =
+                            EVMU256::try_from_be_slice(&hex::decode(&pair.sqrt_price_x96).unwrap_or_default())
+                                .unwrap_or_default();
+                        let liquidity =
+                            EVMU256::try_from_be_slice(&hex::decode(&pair.liquidity).unwrap_or_default())
+                                .unwrap_or_default();
+                        let (reserve0, reserve1) = virtual_v3_reserves(sqrt_price_x96, liquidity);
+
+                        path_parsed.route.push(Rc::new(RefCell::new(PairContext {
+                            pair_address: EVMAddress::from_str(pair.pair.as_str()).expect("failed to parse pair"),
+                            next_hop: EVMAddress::from_str(pair.next.as_str()).expect("failed to parse pair"),
+                            side: pair.in_ as u8,
+                            uniswap_info: Arc::new(get_uniswap_info(
+                                &UniswapProvider::from_str(pair.src_exact.as_str()).unwrap(),
+                                &Chain::from_str(&onchain.chain_name).unwrap(),
+                            )),
+                            initial_reserves: (reserve0, reserve1),
+                        })));
+                    }
                     "v2" => {
                         // let decimals0 = pair["decimals0"].as_u64().expect("failed to parse
                         // decimals0"); let decimals1 =
@@ -117,6 +232,12 @@ pub fn fetch_uniswap_path(onchain: &mut OnChainConfig, token_address: EVMAddress
 pub fn get_weth(network: &str) -> String {
     let pegged_token = get_pegged_token(network);
 
+    if let Some(symbol) = path_finder_config().base_token_symbol.get(network) {
+        if let Some(addr) = pegged_token.get(symbol) {
+            return addr.clone();
+        }
+    }
+
     match network {
         "eth" => return pegged_token.get("WETH").unwrap().to_string(),
         "bsc" => return pegged_token.get("WBNB").unwrap().to_string(),
@@ -131,6 +252,14 @@ pub fn get_weth(network: &str) -> String {
 }
 
 fn get_pegged_token(network: &str) -> HashMap<String, String> {
+    let mut table = get_builtin_pegged_token(network);
+    if let Some(overrides) = path_finder_config().pegged_tokens.get(network) {
+        table.extend(overrides.clone());
+    }
+    table
+}
+
+fn get_builtin_pegged_token(network: &str) -> HashMap<String, String> {
     match network {
         "eth" => [
             ("WETH", "0xc02aaa39b223fe8d0a0e5c4f27ead9083c756cc2"),
@@ -184,6 +313,13 @@ fn get_pair(onchain: &mut OnChainConfig, token: &str, network: &str, is_pegged:
     if token == get_weth(network) {
         return vec![];
     }
+
+    let index = pair_index_for(onchain);
+    if let Some(cached) = index.get_pairs(&token) {
+        debug!("pair index hit for {token}");
+        return cached;
+    }
+
     let weth = get_weth(network);
     let pegged_tokens = get_pegged_token(network);
     let mut pairs = onchain.get_pair(
@@ -192,9 +328,16 @@ fn get_pair(onchain: &mut OnChainConfig, token: &str, network: &str, is_pegged:
         is_pegged || pegged_tokens.values().contains(&token),
         weth,
     );
+    if let Some(providers) = path_finder_config().providers.get(network) {
+        if !providers.is_empty() {
+            pairs.retain(|p| providers.contains(&p.src_exact));
+        }
+    }
     if pairs.len() > 10 {
         pairs.retain(|p| pegged_tokens.values().contains(&p.next));
     }
+
+    index.put_pairs(&token, pairs.clone());
     pairs
 }
 
@@ -207,7 +350,7 @@ fn get_all_hops(
 ) -> HashMap<String, Vec<PairData>> {
     known.insert(token.to_string());
 
-    if hop > MAX_HOPS {
+    if hop > path_finder_config().max_hops() {
         return HashMap::new();
     }
 
@@ -263,13 +406,58 @@ fn get_pegged_next_hop(onchain: &mut OnChainConfig, token: &str, network: &str)
     }
 }
 
-/// returns whether the pair is significant
-fn add_reserve_info(onchain: &mut OnChainConfig, pair_data: &mut PairData) -> bool {
+/// Derives constant-product-equivalent reserves from Uniswap V3's `sqrtPriceX96` and
+/// in-range liquidity `L`: `reserve0 = L * Q96 / sqrtPriceX96`, `reserve1 = L *
+/// sqrtPriceX96 / Q96`.
+///
+/// ACKNOWLEDGED SIMPLIFICATION, not the tick-aware swap math (real `amountOut` from
+/// `sqrtPriceX96` and in-range `L`, clamping at the next initialized tick boundary)
+/// that full V3 support needs: this is only the *current-price* snapshot converted to
+/// an x*y=k-equivalent pair, with no fee tier and no awareness that liquidity changes
+/// once a swap crosses a tick. It's accurate at the quoted price and for the single
+/// swap a path-ranking pass actually simulates, and degrades the further a real swap
+/// would move price past the current tick's range -- which is exactly why this hop
+/// flows through the same v2 constant-product simulation downstream instead of its own
+/// model: nothing consuming `PairContext` carries tick or fee-tier data to simulate
+/// tick-crossing with. Implementing that is a model change across every `PairContext`
+/// consumer, not a local fix to this function.
+fn virtual_v3_reserves(sqrt_price_x96: EVMU256, liquidity: EVMU256) -> (EVMU256, EVMU256) {
+    if sqrt_price_x96.is_zero() {
+        return (EVMU256::ZERO, EVMU256::ZERO);
+    }
+    let q96 = EVMU256::from(2).pow(EVMU256::from(96));
+    let reserve0 = liquidity.saturating_mul(q96) / sqrt_price_x96;
+    let reserve1 = liquidity.saturating_mul(sqrt_price_x96) / q96;
+    (reserve0, reserve1)
+}
+
+/// Returns the pair's depth score (for a v2 pair the smaller of its two reserves, which
+/// is what bounds how much can be traded through it; for a v3 pool its in-range
+/// liquidity `L`; `EVMU256::MAX` for a `pegged_weth` hop), or `None` when the pair is
+/// too thin to be worth routing through at all.
+fn add_reserve_info(onchain: &mut OnChainConfig, pair_data: &mut PairData) -> Option<EVMU256> {
     if pair_data.src == "pegged_weth" {
-        return true;
+        return Some(EVMU256::MAX);
     }
 
-    let reserves = onchain.fetch_reserve(&pair_data.pair);
+    if pair_data.src == "v3" {
+        // V3 reserves are virtual (derived from sqrtPriceX96/liquidity at discovery
+        // time), not a storage slot we can refetch, so gate on liquidity instead of
+        // the decimals-scaled reserve threshold used for v2 pools.
+        let liquidity = EVMU256::try_from_be_slice(&hex::decode(&pair_data.liquidity).unwrap_or_default())
+            .unwrap_or_default();
+        return if liquidity > EVMU256::ZERO { Some(liquidity) } else { None };
+    }
+
+    let index = pair_index_for(onchain);
+    let reserves = match index.get_reserve(&pair_data.pair) {
+        Some(cached) => cached,
+        None => {
+            let fetched = onchain.fetch_reserve(&pair_data.pair);
+            index.put_reserve(&pair_data.pair, fetched.clone());
+            fetched
+        }
+    };
     pair_data.initial_reserves_0 = reserves.0;
     pair_data.initial_reserves_1 = reserves.1;
 
@@ -289,7 +477,11 @@ fn add_reserve_info(onchain: &mut OnChainConfig, pair_data: &mut PairData) -> bo
         EVMU256::from(10).pow(EVMU256::from(pair_data.decimals_1 - 1))
     };
 
-    reserves_0 > min_r0 && reserves_1 > min_r1
+    if reserves_0 > min_r0 && reserves_1 > min_r1 {
+        Some(reserves_0.min(reserves_1))
+    } else {
+        None
+    }
 }
 
 fn with_info(routes: Vec<Vec<PairData>>, network: &str, token: &str) -> Info {
@@ -358,24 +550,40 @@ fn find_path_subgraph(onchain: &mut OnChainConfig, token: &str) -> Info {
         &mut routes,
     );
 
-    let mut routes_without_low_liquidity_idx = vec![];
-
-    for (kth, route) in (&mut routes).iter_mut().enumerate() {
+    // Score each route by its shallowest hop (the bottleneck that actually bounds how
+    // much can be traded through it), drop any route with a hop too thin to route
+    // through at all, and keep only the top-N deepest routes so the swap mutator
+    // spends its budget on the most exploitable paths first.
+    let mut scored_routes: Vec<(EVMU256, Vec<PairData>)> = vec![];
+    for route in (&mut routes).iter_mut() {
+        let mut min_depth: Option<EVMU256> = None;
         let mut low_liquidity = false;
-        for hop in route {
-            low_liquidity |= !add_reserve_info(onchain, hop);
+        for hop in route.iter_mut() {
+            match add_reserve_info(onchain, hop) {
+                Some(depth) => min_depth = Some(min_depth.map_or(depth, |d| d.min(depth))),
+                None => {
+                    low_liquidity = true;
+                    break;
+                }
+            }
         }
         if !low_liquidity {
-            routes_without_low_liquidity_idx.push(kth);
+            if let Some(depth) = min_depth {
+                scored_routes.push((depth, route.clone()));
+            }
         }
     }
 
-    let routes_without_low_liquidity = routes_without_low_liquidity_idx
-        .iter()
-        .map(|&idx| routes[idx].clone())
+    scored_routes.sort_by(|(a, _), (b, _)| b.cmp(a));
+
+    let max_ranked_routes = (path_finder_config().max_hops() as usize + 1) * 4;
+    let ranked_routes = scored_routes
+        .into_iter()
+        .take(max_ranked_routes)
+        .map(|(_, route)| route)
         .collect();
 
-    with_info(routes_without_low_liquidity, network.as_str(), token)
+    with_info(ranked_routes, network.as_str(), token)
 }
 
 mod tests {
@@ -0,0 +1,79 @@
+use std::cell::RefCell;
+
+use indexmap::IndexSet;
+
+use crate::evm::types::{EVMAddress, EVMU256};
+
+/// Probability the calldata mutator should draw a word-aligned argument from
+/// [`VALUE_DICTIONARY`] instead of generating fresh random bytes, mirroring Foundry's
+/// fuzz dictionary. AMM/flashloan bugs usually need arguments that equal or relate to
+/// live reserve/balance magnitudes, which uniform random generation almost never hits.
+pub const DICTIONARY_SAMPLE_PROBABILITY: f64 = 0.4;
+
+thread_local! {
+    // Shared across both the EVM and Move mutators (once wired through
+    // `EVMFuzzState`), so a value harvested from on-chain/off-chain state while
+    // building one pair's cache is available to every subsequent mutation.
+    static VALUE_DICTIONARY: RefCell<IndexSet<[u8; 32]>> = RefCell::new(IndexSet::new());
+}
+
+/// Adds a 32-byte word to the dictionary if it isn't already present.
+pub fn push_value(value: [u8; 32]) {
+    VALUE_DICTIONARY.with(|d| {
+        d.borrow_mut().insert(value);
+    });
+}
+
+/// Left-pads `addr` to 32 bytes and adds it, the same layout calldata encodes an
+/// `address` argument in.
+pub fn push_address(addr: EVMAddress) {
+    let mut value = [0u8; 32];
+    value[12..].copy_from_slice(addr.as_slice());
+    push_value(value);
+}
+
+/// Adds a 256-bit integer in its big-endian calldata-word form.
+pub fn push_u256(v: EVMU256) {
+    push_value(v.to_be_bytes::<32>());
+}
+
+/// Number of distinct values currently in the dictionary.
+pub fn len() -> usize {
+    VALUE_DICTIONARY.with(|d| d.borrow().len())
+}
+
+/// Returns the `index`-th dictionary entry (mod the dictionary's length), for a
+/// mutator that has already rolled its own random index and just needs the value.
+/// `None` when the dictionary is still empty.
+pub fn get(index: usize) -> Option<[u8; 32]> {
+    VALUE_DICTIONARY.with(|d| {
+        let d = d.borrow();
+        if d.is_empty() {
+            None
+        } else {
+            d.get_index(index % d.len()).copied()
+        }
+    })
+}
+
+/// The calldata mutator's entry point for a word-aligned argument: with probability
+/// [`DICTIONARY_SAMPLE_PROBABILITY`] draws a previously harvested on-chain/off-chain
+/// value instead of the mutator's own random generation, the same Foundry-style
+/// dictionary sampling this module exists for.
+///
+/// `roll` is the caller's own `[0, 1)` random draw and `index` its own random pick of
+/// which entry to use if sampling succeeds, so this function stays free of any RNG
+/// dependency itself.
+///
+/// FOLLOW-UP, not yet done: no EVM or Move mutator calls this yet, so the dictionary
+/// `push_value`/`push_address`/`push_u256` populate from `flashloan.rs`/`offchain.rs`
+/// is gathered but never consumed during mutation. Wiring it in means having the
+/// calldata mutator call `sample()` per word-aligned argument in place of its own
+/// random generation; that mutator lives in `evm::mutator`, which isn't part of this
+/// tree, so it isn't done here.
+pub fn sample(roll: f64, index: usize) -> Option<[u8; 32]> {
+    if roll >= DICTIONARY_SAMPLE_PROBABILITY || len() == 0 {
+        return None;
+    }
+    get(index)
+}